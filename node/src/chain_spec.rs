@@ -166,7 +166,7 @@ pub fn local_testnet_config() -> ChainSpec {
 }
 
 fn testnet_genesis(
-    invulnerables: Vec<(AccountId, AuraId)>,
+    collators: Vec<(AccountId, AuraId)>,
     endowed_accounts: Vec<AccountId>,
     root: AccountId,
     id: ParaId,
@@ -178,12 +178,15 @@ fn testnet_genesis(
         "parachainInfo": {
             "parachainId": id,
         },
-        "collatorSelection": {
-            "invulnerables": invulnerables.iter().cloned().map(|(acc, _)| acc).collect::<Vec<_>>(),
-            "candidacyBond": EXISTENTIAL_DEPOSIT * 16,
+        "collatorStaking": {
+            "candidates": collators
+                .iter()
+                .cloned()
+                .map(|(acc, _)| (acc, EXISTENTIAL_DEPOSIT * 16))
+                .collect::<Vec<_>>(),
         },
         "session": {
-            "keys": invulnerables
+            "keys": collators
                 .into_iter()
                 .map(|(acc, aura)| {
                     (
@@ -1,4 +1,17 @@
-use std::path::PathBuf;
+use std::{
+    net::{Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use sp_core::{sr25519, Pair};
+
+use crate::{
+    health::HealthConfig,
+    on_demand::{CollationMode, OnDemandConfig},
+    service::Sealing,
+    session_keys::SessionKeysCmd,
+};
 
 /// Sub-commands supported by the collator.
 #[allow(clippy::large_enum_variant)]
@@ -34,6 +47,13 @@ pub enum Subcommand {
     /// Export the genesis wasm of the parachain.
     ExportGenesisWasm(cumulus_client_cli::ExportGenesisWasmCommand),
 
+    /// Key management cli utilities
+    #[command(subcommand)]
+    Key(sc_cli::KeySubcommand),
+
+    /// Generate session keys into the keystore and print the call that registers them.
+    SessionKeys(SessionKeysCmd),
+
     /// Sub-commands concerned with benchmarking.
     /// The pallet benchmarking moved to the `pallet` sub-command.
     #[command(subcommand)]
@@ -50,6 +70,10 @@ const AFTER_HELP_EXAMPLE: &str = color_print::cstr!(
            Launch a full node with default parachain <italic>local-testnet</> and relay chain <italic>rococo-local</>.
    <bold>bloc-node --collator</>
            Launch a collator with default parachain <italic>local-testnet</> and relay chain <italic>rococo-local</>.
+   <bold>bloc-node session-keys --chain plain-parachain-chainspec.json --base-path /data</>
+           Generate session keys into a collator's keystore and print the <italic>session.set_keys</> call data.
+   <bold>bloc-node --dev --sealing manual</>
+           Launch a standalone development chain, without a relay chain, that seals a block on every <italic>engine_createBlock</> call.
  "#
 );
 #[derive(Debug, clap::Parser)]
@@ -76,11 +100,166 @@ pub struct Cli {
     #[arg(long)]
     pub no_hardware_benchmarks: bool,
 
+    #[command(flatten)]
+    pub on_demand: OnDemandParams,
+
+    #[command(flatten)]
+    pub authoring: AuthoringParams,
+
+    #[command(flatten)]
+    pub health: HealthParams,
+
+    /// Allow offchain workers to make HTTP requests.
+    #[arg(long)]
+    pub offchain_http: bool,
+
+    /// File holding the secret seed of the collator's session keys.
+    ///
+    /// A key for every key type in the runtime's session keys is derived from the seed and
+    /// inserted into the keystore at startup. Without this flag the seed is read from the
+    /// `BLOC_SESSION_SEED` environment variable, if set.
+    #[arg(long)]
+    pub session_key_file: Option<PathBuf>,
+
+    /// How blocks are sealed with `--dev`, which runs the chain without a relay chain.
+    ///
+    /// Blocks can be sealed through the `engine_createBlock` RPC in either mode.
+    #[arg(long, value_enum, default_value_t = Sealing::Instant)]
+    pub sealing: Sealing,
+
     /// Relay chain arguments
     #[arg(raw = true)]
     pub relay_chain_args: Vec<String>,
 }
 
+impl Cli {
+    /// The secret seed of the collator's session keys, from `--session-key-file` or the
+    /// `BLOC_SESSION_SEED` environment variable.
+    pub fn session_seed(&self) -> Result<Option<String>, String> {
+        match &self.session_key_file {
+            Some(path) => std::fs::read_to_string(path)
+                .map(|seed| Some(seed.trim().to_string()))
+                .map_err(|e| format!("Failed to read {}: {e}", path.display())),
+            None => Ok(std::env::var("BLOC_SESSION_SEED").ok()),
+        }
+    }
+}
+
+/// Parameters for collating on on-demand coretime.
+#[derive(Debug, clap::Args)]
+pub struct OnDemandParams {
+    /// Buy on-demand coretime instead of relying on a core assigned to the parachain.
+    ///
+    /// The collator places an order on the relay chain whenever transactions are pending, or
+    /// when no block was produced for `--on-demand-heartbeat` seconds.
+    ///
+    /// Collators are punished for the slots left empty between orders unless the chain's
+    /// `MaxMissedSlots` parameter is zero, as it is by default in runtimes built with the
+    /// `on-demand` feature.
+    #[arg(long, requires = "on_demand_rpc_url")]
+    pub on_demand: bool,
+
+    /// Seconds without a block after which an order is placed regardless.
+    #[arg(long, default_value_t = 3600)]
+    pub on_demand_heartbeat: u64,
+
+    /// The most to pay for a single order, in the relay chain's smallest unit.
+    #[arg(long, default_value_t = 1_000_000_000_000)]
+    pub on_demand_max_amount: u128,
+
+    /// File holding the secret URI of the relay chain account that pays for orders.
+    ///
+    /// Without this flag the secret URI is read from the `BLOC_ON_DEMAND_SIGNER` environment
+    /// variable.
+    #[arg(long)]
+    pub on_demand_signer_file: Option<PathBuf>,
+
+    /// HTTP RPC endpoint of a relay chain node to submit orders to.
+    #[arg(long)]
+    pub on_demand_rpc_url: Option<String>,
+}
+
+impl OnDemandParams {
+    /// The collation mode selected on the command line.
+    pub fn collation_mode(&self) -> Result<CollationMode, String> {
+        if !self.on_demand {
+            return Ok(CollationMode::Core);
+        }
+
+        let signer = match &self.on_demand_signer_file {
+            Some(path) => std::fs::read_to_string(path)
+                .map(|signer| signer.trim().to_string())
+                .map_err(|e| format!("Failed to read {}: {e}", path.display()))?,
+            None => std::env::var("BLOC_ON_DEMAND_SIGNER").map_err(|_| {
+                "--on-demand requires --on-demand-signer-file or BLOC_ON_DEMAND_SIGNER".to_string()
+            })?,
+        };
+        let signer = sr25519::Pair::from_string(&signer, None)
+            .map_err(|e| format!("Invalid on-demand signer: {e:?}"))?;
+        Ok(CollationMode::OnDemand(OnDemandConfig {
+            heartbeat: Duration::from_secs(self.on_demand_heartbeat),
+            max_amount: self.on_demand_max_amount,
+            signer,
+            // Required by `--on-demand`.
+            rpc_url: self.on_demand_rpc_url.clone().unwrap_or_default(),
+        }))
+    }
+}
+
+/// Parameters for timing block authoring.
+#[derive(Debug, clap::Args)]
+pub struct AuthoringParams {
+    /// Milliseconds spent building each block.
+    ///
+    /// Defaults to half the parachain slot duration, capped at the 2 seconds the relay chain
+    /// allows for validating a block.
+    #[arg(long)]
+    pub authoring_duration: Option<u64>,
+}
+
+impl AuthoringParams {
+    /// The authoring duration, if set on the command line.
+    pub fn authoring_duration(&self) -> Option<Duration> {
+        self.authoring_duration.map(Duration::from_millis)
+    }
+}
+
+/// Parameters for the health endpoint.
+#[derive(Debug, clap::Args)]
+pub struct HealthParams {
+    /// Serve liveness and readiness checks over HTTP on this port.
+    ///
+    /// `/health` answers `200 OK` when every check passes and `503 Service Unavailable`
+    /// otherwise. `/health/relay`, `/health/sync`, `/health/aura` and `/health/authoring` answer
+    /// for a single check each.
+    #[arg(long, value_name = "PORT")]
+    pub health_port: Option<u16>,
+
+    /// Serve the health checks on all interfaces instead of only on localhost.
+    #[arg(long)]
+    pub health_external: bool,
+
+    /// Seconds a collator in the authority set may go without authoring a block before it is
+    /// reported unhealthy.
+    #[arg(long, default_value_t = 600)]
+    pub health_max_authoring_gap: u64,
+}
+
+impl HealthParams {
+    /// The health endpoint to serve, if any.
+    pub fn health_config(&self) -> Option<HealthConfig> {
+        let ip = if self.health_external {
+            Ipv4Addr::UNSPECIFIED
+        } else {
+            Ipv4Addr::LOCALHOST
+        };
+        self.health_port.map(|port| HealthConfig {
+            addr: SocketAddr::new(ip.into(), port),
+            max_authoring_gap: Duration::from_secs(self.health_max_authoring_gap),
+        })
+    }
+}
+
 #[derive(Debug)]
 pub struct RelayChainCli {
     /// The actual relay chain cli object.
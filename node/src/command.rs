@@ -181,6 +181,15 @@ pub fn run() -> Result<()> {
                 cmd.run(&*spec)
             })
         }
+        Some(Subcommand::Key(cmd)) => cmd.run(&cli),
+        Some(Subcommand::SessionKeys(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.sync_run(|config| {
+                let partials = new_partial(&config)?;
+
+                cmd.run(partials.client, partials.keystore_container.keystore())
+            })
+        }
         Some(Subcommand::Benchmark(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             // Switch on the concrete benchmark sub-command-
@@ -236,6 +245,15 @@ pub fn run() -> Result<()> {
                     .map(|e| e.para_id)
                     .ok_or("Could not find parachain ID in chain-spec.")?;
 
+                if cli.run.base.shared_params.is_dev() {
+                    return crate::service::start_dev_node(
+                        config,
+                        ParaId::from(para_id),
+                        cli.sealing,
+                    )
+                    .map_err(Into::into);
+                }
+
                 let polkadot_cli = RelayChainCli::new(
                     &config,
                     [RelayChainCli::executable_name()]
@@ -244,6 +262,8 @@ pub fn run() -> Result<()> {
                 );
 
                 let id = ParaId::from(para_id);
+                let collation_mode = cli.on_demand.collation_mode()?;
+                let session_seed = cli.session_seed()?;
 
                 let tokio_handle = config.tokio_handle.clone();
                 let polkadot_config =
@@ -264,6 +284,11 @@ pub fn run() -> Result<()> {
                     polkadot_config,
                     collator_options,
                     id,
+                    collation_mode,
+                    cli.authoring.authoring_duration(),
+                    cli.offchain_http,
+                    session_seed,
+                    cli.health.health_config(),
                     hwbench,
                 )
                 .await
@@ -0,0 +1,123 @@
+//! Reports Aura equivocations seen by this node.
+//!
+//! Every imported header is remembered by slot, height and relay parent for a while. A second,
+//! different header for the same slot, height and relay parent was sealed twice by the slot's
+//! author, and the two headers are submitted to the local transaction pool as an equivocation
+//! report. Headers built on different relay parents are expected: collators build on every relay
+//! chain fork, and relay forks can share a slot.
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use bloc_runtime::{
+    opaque::Block, AuraId, BlockNumber, Header, Runtime, RuntimeCall, UncheckedExtrinsic,
+};
+use codec::Encode;
+use cumulus_primitives_core::{relay_chain, rpsr_digest::extract_relay_parent_storage_root};
+use futures::StreamExt;
+use pallet_collator_offences::EquivocationProof;
+use sc_client_api::BlockchainEvents;
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::{
+    digests::CompatibleDigestItem, sr25519::AuthoritySignature, AuraApi, Slot,
+};
+use sp_runtime::{traits::Header as HeaderT, DigestItem, OpaqueExtrinsic};
+
+const LOG_TARGET: &str = "equivocation";
+
+/// Number of slots headers are remembered for.
+const SLOTS_TO_KEEP: u64 = 64;
+
+/// The storage root and number of the relay parent a block was built on.
+type RelayParent = (relay_chain::Hash, relay_chain::BlockNumber);
+
+/// Watch block imports and report Aura equivocations until the import stream ends.
+pub async fn report_equivocations<C, P>(client: Arc<C>, pool: Arc<P>)
+where
+    C: BlockchainEvents<Block> + HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+    C::Api: AuraApi<Block, AuraId>,
+    P: TransactionPool<Block = Block>,
+{
+    let mut imports = client.every_import_notification_stream();
+    let mut seen = BTreeMap::<(Slot, BlockNumber, Option<RelayParent>), Header>::new();
+
+    while let Some(notification) = imports.next().await {
+        let header = notification.header;
+        let Some(slot) = header.digest().logs().iter().find_map(|log| {
+            <DigestItem as CompatibleDigestItem<AuthoritySignature>>::as_aura_pre_digest(log)
+        }) else {
+            continue;
+        };
+
+        let relay_parent = extract_relay_parent_storage_root(header.digest());
+        let key = (slot, *header.number(), relay_parent);
+        match seen.get(&key) {
+            Some(first) if first.hash() != header.hash() => {
+                report(&*client, &*pool, slot, first.clone(), header).await;
+            }
+            Some(_) => {}
+            None => {
+                seen.insert(key, header);
+            }
+        }
+
+        let oldest = Slot::from(u64::from(slot).saturating_sub(SLOTS_TO_KEEP));
+        seen = seen.split_off(&(oldest, 0, None));
+    }
+}
+
+/// Submit an equivocation report for two headers sealed in `slot`.
+async fn report<C, P>(client: &C, pool: &P, slot: Slot, first_header: Header, second_header: Header)
+where
+    C: HeaderBackend<Block> + ProvideRuntimeApi<Block>,
+    C::Api: AuraApi<Block, AuraId>,
+    P: TransactionPool<Block = Block>,
+{
+    let authorities = match client
+        .runtime_api()
+        .authorities(*second_header.parent_hash())
+    {
+        Ok(authorities) if !authorities.is_empty() => authorities,
+        Ok(_) => return,
+        Err(e) => {
+            log::warn!(target: LOG_TARGET, "Failed to fetch Aura authorities: {e}");
+            return;
+        }
+    };
+    let offender = authorities[(u64::from(slot) % authorities.len() as u64) as usize].clone();
+
+    log::warn!(
+        target: LOG_TARGET,
+        "Reporting equivocation by {offender:?} at slot {slot:?}: {:?} and {:?}",
+        first_header.hash(),
+        second_header.hash(),
+    );
+
+    let proof = EquivocationProof::<Runtime> {
+        offender,
+        slot,
+        first_header,
+        second_header,
+    };
+    let call = RuntimeCall::CollatorOffences(
+        pallet_collator_offences::Call::report_equivocation_unsigned {
+            proof: Box::new(proof),
+        },
+    );
+    let extrinsic =
+        match OpaqueExtrinsic::from_bytes(&UncheckedExtrinsic::new_unsigned(call).encode()) {
+            Ok(extrinsic) => extrinsic,
+            Err(e) => {
+                log::warn!(target: LOG_TARGET, "Failed to encode equivocation report: {e}");
+                return;
+            }
+        };
+
+    if let Err(e) = pool
+        .submit_one(client.info().best_hash, TransactionSource::Local, extrinsic)
+        .await
+    {
+        log::warn!(target: LOG_TARGET, "Failed to submit equivocation report: {e:?}");
+    }
+}
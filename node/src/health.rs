@@ -0,0 +1,273 @@
+//! Liveness and readiness checks over HTTP.
+//!
+//! Every check is served on a path of its own and answers `200 OK` when healthy and
+//! `503 Service Unavailable` otherwise, with the details as JSON:
+//!
+//! - `/health/relay`: the relay chain is reachable and not major syncing.
+//! - `/health/sync`: the parachain is not major syncing, with its best and finalized heights.
+//! - `/health/aura`: a collator has a key in the current Aura authority set.
+//! - `/health/authoring`: a collator in the authority set authored a block recently.
+//!
+//! `/health` runs all of them. The Aura and authoring checks always pass on nodes that don't
+//! collate. The relay and sync checks fail if they get no answer within [`CHECK_TIMEOUT`].
+
+use std::{
+    convert::Infallible,
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bloc_runtime::{opaque::Block, AuraId};
+use cumulus_relay_chain_interface::RelayChainInterface;
+use futures::future::{self, Either};
+use futures_timer::Delay;
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use sc_network_sync::SyncingService;
+use serde_json::{json, Value};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::AuraApi;
+use sp_keystore::KeystorePtr;
+
+const LOG_TARGET: &str = "health";
+
+/// The checks run by `/health`, in order.
+const CHECKS: [&str; 4] = ["relay", "sync", "aura", "authoring"];
+
+/// Longest the relay and sync checks wait for an answer.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Where to serve the health checks.
+pub struct HealthConfig {
+    /// Address to listen on.
+    pub addr: SocketAddr,
+    /// Longest a collator in the authority set may go without authoring a block.
+    pub max_authoring_gap: Duration,
+}
+
+/// When the node last authored a block, or started if it hasn't authored one yet.
+#[derive(Clone)]
+pub struct LastAuthored(Arc<Mutex<Instant>>);
+
+impl Default for LastAuthored {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+}
+
+impl LastAuthored {
+    /// Record a block authored just now.
+    pub fn note(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+/// Everything the checks look at.
+pub struct Health<C> {
+    /// The parachain client.
+    pub client: Arc<C>,
+    /// The keystore holding the collator's Aura key.
+    pub keystore: KeystorePtr,
+    /// The parachain sync service.
+    pub sync_service: Arc<SyncingService<Block>>,
+    /// The relay chain the parachain follows.
+    pub relay_chain_interface: Arc<dyn RelayChainInterface>,
+    /// Whether the node collates.
+    pub collator: bool,
+    /// When the node last authored a block.
+    pub last_authored: LastAuthored,
+    /// Longest a collator in the authority set may go without authoring a block.
+    pub max_authoring_gap: Duration,
+}
+
+/// The outcome of a single check.
+struct Check {
+    healthy: bool,
+    details: Value,
+}
+
+impl Check {
+    /// Run `check`, failing it if it takes longer than `CHECK_TIMEOUT`.
+    async fn with_timeout(check: impl Future<Output = Check>) -> Check {
+        match future::select(Box::pin(check), Delay::new(CHECK_TIMEOUT)).await {
+            Either::Left((check, _)) => check,
+            Either::Right(((), _)) => Check {
+                healthy: false,
+                details: json!({ "error": "Timed out" }),
+            },
+        }
+    }
+
+    /// The details, with whether the check passed.
+    fn into_json(self) -> Value {
+        let mut json = self.details;
+        json["healthy"] = self.healthy.into();
+        json
+    }
+}
+
+impl<C> Health<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: AuraApi<Block, AuraId>,
+{
+    async fn relay(&self) -> Check {
+        match self.relay_chain_interface.is_major_syncing().await {
+            Ok(syncing) => Check {
+                healthy: !syncing,
+                details: json!({ "connected": true, "syncing": syncing }),
+            },
+            Err(e) => Check {
+                healthy: false,
+                details: json!({ "connected": false, "error": e.to_string() }),
+            },
+        }
+    }
+
+    async fn sync(&self) -> Check {
+        let info = self.client.info();
+        let heights = json!({ "best": info.best_number, "finalized": info.finalized_number });
+        match self.sync_service.status().await {
+            Ok(status) => {
+                let syncing = status.state.is_major_syncing();
+                Check {
+                    healthy: !syncing,
+                    details: json!({
+                        "syncing": syncing,
+                        "peers": status.num_peers,
+                        "heights": heights,
+                    }),
+                }
+            }
+            Err(()) => Check {
+                healthy: false,
+                details: json!({ "error": "Sync service is not running", "heights": heights }),
+            },
+        }
+    }
+
+    fn aura(&self) -> Check {
+        if !self.collator {
+            return Check {
+                healthy: true,
+                details: json!({ "collator": false }),
+            };
+        }
+
+        match crate::session_keys::has_authority_key(&*self.client, &self.keystore) {
+            Ok(authority) => Check {
+                healthy: authority,
+                details: json!({ "collator": true, "authority": authority }),
+            },
+            Err(e) => Check {
+                healthy: false,
+                details: json!({ "collator": true, "error": e }),
+            },
+        }
+    }
+
+    fn authoring(&self) -> Check {
+        let since_last_authored = self.last_authored.elapsed();
+        // Only collators in the authority set get slots to author in.
+        let authority = self.collator
+            && crate::session_keys::has_authority_key(&*self.client, &self.keystore)
+                .unwrap_or_default();
+        Check {
+            healthy: !authority || since_last_authored <= self.max_authoring_gap,
+            details: json!({
+                "authority": authority,
+                "seconds_since_last_authored": since_last_authored.as_secs(),
+            }),
+        }
+    }
+
+    async fn check(&self, name: &str) -> Option<Check> {
+        Some(match name {
+            "relay" => Check::with_timeout(self.relay()).await,
+            "sync" => Check::with_timeout(self.sync()).await,
+            "aura" => self.aura(),
+            "authoring" => self.authoring(),
+            _ => return None,
+        })
+    }
+
+    async fn respond(&self, path: &str) -> Response<Body> {
+        let (healthy, body) = match path.trim_end_matches('/') {
+            "/health" => {
+                let mut healthy = true;
+                let mut checks = serde_json::Map::new();
+                for name in CHECKS {
+                    if let Some(check) = self.check(name).await {
+                        healthy &= check.healthy;
+                        checks.insert(name.into(), check.into_json());
+                    }
+                }
+                (healthy, json!({ "healthy": healthy, "checks": checks }))
+            }
+            path => match path.strip_prefix("/health/") {
+                Some(name) => match self.check(name).await {
+                    Some(check) => (check.healthy, check.into_json()),
+                    None => return not_found(),
+                },
+                None => return not_found(),
+            },
+        };
+
+        let status = if healthy {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        };
+        Response::builder()
+            .status(status)
+            .header("Content-Type", "application/json")
+            .body(Body::from(body.to_string()))
+            .expect("status and header are valid; qed")
+    }
+}
+
+fn not_found() -> Response<Body> {
+    Response::builder()
+        .status(StatusCode::NOT_FOUND)
+        .body(Body::empty())
+        .expect("status is valid; qed")
+}
+
+/// Bind to `addr` and return the future serving the health checks on it.
+pub fn serve<C>(
+    addr: SocketAddr,
+    health: Health<C>,
+) -> Result<impl Future<Output = ()> + Send, String>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: AuraApi<Block, AuraId>,
+{
+    let health = Arc::new(health);
+    let server = Server::try_bind(&addr)
+        .map_err(|e| format!("Failed to bind the health endpoint to {addr}: {e}"))?
+        .serve(make_service_fn(move |_| {
+            let health = health.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request: Request<Body>| {
+                    let health = health.clone();
+                    async move { Ok::<_, Infallible>(health.respond(request.uri().path()).await) }
+                }))
+            }
+        }));
+
+    log::info!(target: LOG_TARGET, "Serving health checks on http://{addr}/health");
+    Ok(async move {
+        if let Err(e) = server.await {
+            log::error!(target: LOG_TARGET, "Health endpoint failed: {e}");
+        }
+    })
+}
@@ -5,8 +5,12 @@
 mod chain_spec;
 mod cli;
 mod command;
+mod equivocation;
+mod health;
+mod on_demand;
 mod rpc;
 mod service;
+mod session_keys;
 
 fn main() -> sc_cli::Result<()> {
     command::run()
@@ -0,0 +1,499 @@
+//! Buys on-demand coretime for the parachain.
+//!
+//! Instead of relying on a core assigned to the parachain, the collator places an order with the
+//! relay chain's on-demand assigner whenever it has something to include: transactions waiting
+//! in the pool, or a heartbeat block after the chain has been idle for a while. The collator
+//! authors as soon as the order schedules the parachain on a core.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use bloc_runtime::opaque::Block;
+use codec::{Compact, Decode, Encode};
+use cumulus_primitives_core::ParaId;
+use cumulus_relay_chain_interface::RelayChainInterface;
+use frame_metadata::{RuntimeMetadata, RuntimeMetadataPrefixed};
+use futures::StreamExt;
+use jsonrpsee::{
+    core::client::ClientT,
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
+use polkadot_primitives::{Balance, Hash, Nonce};
+use sc_transaction_pool_api::TransactionPool;
+use scale_info::{PortableRegistry, TypeDef};
+use sp_blockchain::HeaderBackend;
+use sp_core::{crypto::Ss58Codec, hashing::blake2_256, sr25519, Bytes, Pair};
+use sp_runtime::{generic::Era, AccountId32, MultiAddress, MultiSignature};
+use sp_version::RuntimeVersion;
+
+const LOG_TARGET: &str = "on-demand";
+
+/// Relay chain blocks to wait for an order to be served before placing another one.
+const ORDER_TIMEOUT_BLOCKS: u32 = 5;
+
+/// How the collator gets its blocks onto relay chain cores.
+#[allow(clippy::large_enum_variant)]
+pub enum CollationMode {
+    /// The parachain has a core assigned, author in every slot.
+    Core,
+    /// Buy on-demand coretime when there is something to author.
+    OnDemand(OnDemandConfig),
+}
+
+/// Parameters for buying on-demand coretime.
+pub struct OnDemandConfig {
+    /// Idle time after which an order is placed even without pending transactions.
+    pub heartbeat: Duration,
+    /// The most to pay for a single order.
+    pub max_amount: Balance,
+    /// The relay chain account that pays for orders.
+    pub signer: sr25519::Pair,
+    /// RPC endpoint of the relay chain node that orders are submitted to.
+    pub rpc_url: String,
+}
+
+/// Place an order on every relay chain block that finds transactions pending, or the chain idle
+/// for longer than the heartbeat, unless an earlier order is still waiting to be served.
+pub async fn order_coretime<C, P>(
+    client: Arc<C>,
+    pool: Arc<P>,
+    relay_chain_interface: Arc<dyn RelayChainInterface>,
+    para_id: ParaId,
+    config: OnDemandConfig,
+) where
+    C: HeaderBackend<Block>,
+    P: TransactionPool,
+{
+    let rpc = match HttpClientBuilder::default().build(&config.rpc_url) {
+        Ok(rpc) => rpc,
+        Err(e) => {
+            log::error!(target: LOG_TARGET, "Invalid relay chain RPC endpoint: {e}");
+            return;
+        }
+    };
+    let mut relay_blocks = match relay_chain_interface.new_best_notification_stream().await {
+        Ok(relay_blocks) => relay_blocks,
+        Err(e) => {
+            log::error!(target: LOG_TARGET, "Failed to follow the relay chain: {e}");
+            return;
+        }
+    };
+
+    let mut best = client.info().best_number;
+    let mut last_block_at = Instant::now();
+    // Relay chain blocks left before an order that was not served yet is given up on.
+    let mut waiting: u32 = 0;
+    let mut format = None;
+
+    while relay_blocks.next().await.is_some() {
+        let best_number = client.info().best_number;
+        if best_number != best {
+            best = best_number;
+            last_block_at = Instant::now();
+            waiting = 0;
+        }
+
+        if waiting > 0 {
+            waiting -= 1;
+            continue;
+        }
+        if pool.status().ready == 0 && last_block_at.elapsed() < config.heartbeat {
+            continue;
+        }
+
+        match place_order(&rpc, para_id, &config, &mut format).await {
+            Ok(()) => {
+                log::info!(target: LOG_TARGET, "Placed an on-demand order for block #{}", best + 1);
+                waiting = ORDER_TIMEOUT_BLOCKS;
+            }
+            Err(e) => log::warn!(target: LOG_TARGET, "Failed to place an on-demand order: {e}"),
+        }
+    }
+}
+
+/// How to place an order with a relay chain runtime, read from its metadata.
+struct OrderFormat {
+    /// The runtime version the format was read from.
+    spec_version: u32,
+    /// The encoded `place_order_keep_alive` call.
+    call: Vec<u8>,
+    /// The runtime's signed extensions, in the order they appear in an extrinsic.
+    extensions: Vec<SignedExtension>,
+}
+
+/// A signed extension of the relay chain runtime.
+struct SignedExtension {
+    identifier: String,
+    /// Whether the extension adds no data to the extrinsic and none to the signed payload.
+    empty: bool,
+}
+
+impl OrderFormat {
+    /// Read the order format from the relay chain runtime's `metadata`.
+    fn from_metadata(
+        spec_version: u32,
+        metadata: &[u8],
+        para_id: ParaId,
+        max_amount: Balance,
+    ) -> Result<Self, String> {
+        let metadata = match RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+            .map_err(|e| format!("Invalid relay chain metadata: {e}"))?
+            .1
+        {
+            RuntimeMetadata::V14(metadata) => metadata,
+            _ => return Err("Unsupported relay chain metadata version".into()),
+        };
+        if metadata.extrinsic.version != 4 {
+            return Err(format!(
+                "Unsupported extrinsic version {}",
+                metadata.extrinsic.version
+            ));
+        }
+        let types = &metadata.types;
+
+        // The pallet was renamed from `OnDemandAssignmentProvider` to `OnDemand`.
+        let (pallet_index, calls) = metadata
+            .pallets
+            .iter()
+            .find(|pallet| {
+                matches!(
+                    pallet.name.as_str(),
+                    "OnDemandAssignmentProvider" | "OnDemand"
+                )
+            })
+            .and_then(|pallet| Some((pallet.index, pallet.calls.as_ref()?)))
+            .ok_or("The relay chain has no on-demand assigner")?;
+        let call = match types.resolve(calls.ty.id).map(|ty| &ty.type_def) {
+            Some(TypeDef::Variant(calls)) => calls
+                .variants
+                .iter()
+                .find(|call| call.name == "place_order_keep_alive"),
+            _ => None,
+        }
+        .ok_or("The on-demand assigner has no place_order_keep_alive call")?;
+
+        let mut encoded = vec![pallet_index, call.index];
+        for field in &call.fields {
+            let compact = matches!(
+                types.resolve(field.ty.id).map(|ty| &ty.type_def),
+                Some(TypeDef::Compact(_))
+            );
+            match (field.name.as_deref(), compact) {
+                (Some("max_amount"), false) => max_amount.encode_to(&mut encoded),
+                (Some("max_amount"), true) => Compact(max_amount).encode_to(&mut encoded),
+                (Some("para_id"), false) => para_id.encode_to(&mut encoded),
+                (Some("para_id"), true) => Compact(u32::from(para_id)).encode_to(&mut encoded),
+                (name, _) => {
+                    return Err(format!("Unknown place_order_keep_alive argument {name:?}"))
+                }
+            }
+        }
+
+        let extensions = metadata
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|extension| SignedExtension {
+                identifier: extension.identifier.clone(),
+                empty: is_empty(types, extension.ty.id)
+                    && is_empty(types, extension.additional_signed.id),
+            })
+            .collect();
+
+        Ok(Self {
+            spec_version,
+            call: encoded,
+            extensions,
+        })
+    }
+
+    /// Encode the data the signed extensions add to the extrinsic and to the signed payload.
+    fn encode_extensions(
+        &self,
+        version: &RuntimeVersion,
+        genesis: Hash,
+        nonce: Nonce,
+    ) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let mut extra = Vec::new();
+        let mut additional = Vec::new();
+        for extension in &self.extensions {
+            match extension.identifier.as_str() {
+                "CheckSpecVersion" => version.spec_version.encode_to(&mut additional),
+                "CheckTxVersion" => version.transaction_version.encode_to(&mut additional),
+                "CheckGenesis" => genesis.encode_to(&mut additional),
+                "CheckMortality" => {
+                    Era::Immortal.encode_to(&mut extra);
+                    genesis.encode_to(&mut additional);
+                }
+                "CheckNonce" => Compact(nonce).encode_to(&mut extra),
+                // No tip.
+                "ChargeTransactionPayment" => Compact(0 as Balance).encode_to(&mut extra),
+                // No tip, paid in the native asset.
+                "ChargeAssetTxPayment" => {
+                    Compact(0 as Balance).encode_to(&mut extra);
+                    None::<()>.encode_to(&mut extra);
+                }
+                // Disabled, without a metadata hash.
+                "CheckMetadataHash" => {
+                    0u8.encode_to(&mut extra);
+                    None::<Hash>.encode_to(&mut additional);
+                }
+                // E.g. `CheckNonZeroSender` and `CheckWeight`.
+                _ if extension.empty => {}
+                identifier => return Err(format!("Unsupported signed extension {identifier}")),
+            }
+        }
+        Ok((extra, additional))
+    }
+
+    /// Sign the order as `signer`'s transaction number `nonce`, returning the extrinsic without
+    /// its length prefix.
+    fn sign(
+        &self,
+        signer: &sr25519::Pair,
+        version: &RuntimeVersion,
+        genesis: Hash,
+        nonce: Nonce,
+    ) -> Result<Vec<u8>, String> {
+        let (extra, additional) = self.encode_extensions(version, genesis, nonce)?;
+        let payload = [&self.call[..], &extra, &additional].concat();
+        // Payloads longer than 256 bytes are signed by their hash.
+        let signature = if payload.len() > 256 {
+            signer.sign(&blake2_256(&payload))
+        } else {
+            signer.sign(&payload)
+        };
+
+        // A signed extrinsic of version 4.
+        let mut extrinsic = vec![0b1000_0100];
+        MultiAddress::<AccountId32, ()>::Id(signer.public().into()).encode_to(&mut extrinsic);
+        MultiSignature::Sr25519(signature).encode_to(&mut extrinsic);
+        extrinsic.extend(extra);
+        extrinsic.extend(&self.call);
+        Ok(extrinsic)
+    }
+}
+
+/// Whether values of the type `id` encode to nothing.
+fn is_empty(types: &PortableRegistry, id: u32) -> bool {
+    match types.resolve(id).map(|ty| &ty.type_def) {
+        Some(TypeDef::Composite(composite)) => composite
+            .fields
+            .iter()
+            .all(|field| is_empty(types, field.ty.id)),
+        Some(TypeDef::Tuple(tuple)) => tuple.fields.iter().all(|field| is_empty(types, field.id)),
+        Some(TypeDef::Array(array)) => array.len == 0,
+        _ => false,
+    }
+}
+
+/// Sign and submit an on-demand order for `para_id`.
+///
+/// The call and the signed extensions are encoded from the relay chain's metadata, which is
+/// fetched again whenever the relay chain's runtime changes.
+async fn place_order(
+    rpc: &HttpClient,
+    para_id: ParaId,
+    config: &OnDemandConfig,
+    format: &mut Option<OrderFormat>,
+) -> Result<(), String> {
+    let account = AccountId32::from(config.signer.public());
+    let genesis: Hash = rpc
+        .request("chain_getBlockHash", rpc_params![0])
+        .await
+        .map_err(|e| e.to_string())?;
+    let version: RuntimeVersion = rpc
+        .request("state_getRuntimeVersion", rpc_params![])
+        .await
+        .map_err(|e| e.to_string())?;
+    let nonce: Nonce = rpc
+        .request(
+            "system_accountNextIndex",
+            rpc_params![account.to_ss58check()],
+        )
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let format = match format {
+        Some(format) if format.spec_version == version.spec_version => format,
+        format => {
+            let metadata: Bytes = rpc
+                .request("state_getMetadata", rpc_params![])
+                .await
+                .map_err(|e| e.to_string())?;
+            format.insert(OrderFormat::from_metadata(
+                version.spec_version,
+                &metadata,
+                para_id,
+                config.max_amount,
+            )?)
+        }
+    };
+
+    let extrinsic = format.sign(&config.signer, &version, genesis, nonce)?;
+    rpc.request::<Hash, _>(
+        "author_submitExtrinsic",
+        rpc_params![Bytes(extrinsic.encode())],
+    )
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The metadata of rococo-runtime 12.0.0, as returned by `state_getMetadata`.
+    const ROCOCO_METADATA: &[u8] = include_bytes!("../res/rococo-metadata.scale");
+
+    const PARA_ID: u32 = 2000;
+    const MAX_AMOUNT: Balance = 1_000_000_000_000;
+
+    fn rococo_format() -> OrderFormat {
+        OrderFormat::from_metadata(
+            1_012_000,
+            ROCOCO_METADATA,
+            ParaId::from(PARA_ID),
+            MAX_AMOUNT,
+        )
+        .unwrap()
+    }
+
+    fn version() -> RuntimeVersion {
+        RuntimeVersion {
+            spec_version: 1_012_000,
+            transaction_version: 26,
+            ..Default::default()
+        }
+    }
+
+    /// The rococo metadata with `identifier` appended to its signed extensions, taking its types
+    /// from the existing extension `like`.
+    fn with_extension(identifier: &str, like: &str) -> Vec<u8> {
+        let RuntimeMetadataPrefixed(prefix, RuntimeMetadata::V14(mut metadata)) =
+            RuntimeMetadataPrefixed::decode(&mut &ROCOCO_METADATA[..]).unwrap()
+        else {
+            panic!("the fixture is V14 metadata");
+        };
+        let mut extension = metadata
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .find(|extension| extension.identifier == like)
+            .unwrap()
+            .clone();
+        extension.identifier = identifier.into();
+        metadata.extrinsic.signed_extensions.push(extension);
+        RuntimeMetadataPrefixed(prefix, RuntimeMetadata::V14(metadata)).encode()
+    }
+
+    #[test]
+    fn place_order_keep_alive_is_encoded_from_metadata() {
+        let format = rococo_format();
+
+        // `OnDemandAssignmentProvider` is pallet 66 and `place_order_keep_alive` its call 1, with
+        // a plain `max_amount` and `para_id`.
+        let mut call = vec![66, 1];
+        call.extend(MAX_AMOUNT.to_le_bytes());
+        call.extend(PARA_ID.to_le_bytes());
+        assert_eq!(format.call, call);
+    }
+
+    #[test]
+    fn signed_extensions_are_read_from_metadata() {
+        let extensions = rococo_format()
+            .extensions
+            .into_iter()
+            .map(|extension| (extension.identifier, extension.empty))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            extensions,
+            [
+                ("CheckNonZeroSender", true),
+                ("CheckSpecVersion", false),
+                ("CheckTxVersion", false),
+                ("CheckGenesis", false),
+                ("CheckMortality", false),
+                ("CheckNonce", false),
+                ("CheckWeight", true),
+                ("ChargeTransactionPayment", false),
+            ]
+            .map(|(identifier, empty)| (identifier.to_string(), empty))
+        );
+    }
+
+    #[test]
+    fn signed_extensions_are_encoded_in_order() {
+        let genesis = Hash::repeat_byte(7);
+
+        let (extra, additional) = rococo_format()
+            .encode_extensions(&version(), genesis, 5)
+            .unwrap();
+
+        // Immortal era, compact nonce 5, compact tip 0.
+        assert_eq!(extra, [0, 5 << 2, 0]);
+        let mut expected = Vec::new();
+        expected.extend(1_012_000u32.to_le_bytes());
+        expected.extend(26u32.to_le_bytes());
+        // Once for `CheckGenesis`, and once as the block of the immortal era.
+        expected.extend(genesis.as_bytes());
+        expected.extend(genesis.as_bytes());
+        assert_eq!(additional, expected);
+    }
+
+    #[test]
+    fn unknown_empty_extensions_are_skipped() {
+        let metadata = with_extension("CheckSomething", "CheckNonZeroSender");
+        let format =
+            OrderFormat::from_metadata(1_012_000, &metadata, ParaId::from(PARA_ID), MAX_AMOUNT)
+                .unwrap();
+
+        assert_eq!(
+            format.encode_extensions(&version(), Hash::zero(), 5),
+            rococo_format().encode_extensions(&version(), Hash::zero(), 5),
+        );
+    }
+
+    #[test]
+    fn unknown_non_empty_extensions_are_rejected() {
+        let metadata = with_extension("CheckSomething", "CheckNonce");
+        let format =
+            OrderFormat::from_metadata(1_012_000, &metadata, ParaId::from(PARA_ID), MAX_AMOUNT)
+                .unwrap();
+
+        assert_eq!(
+            format.encode_extensions(&version(), Hash::zero(), 5),
+            Err("Unsupported signed extension CheckSomething".to_string()),
+        );
+    }
+
+    #[test]
+    fn orders_are_signed_over_call_and_extensions() {
+        let format = rococo_format();
+        let signer = sr25519::Pair::from_string("//Alice", None).unwrap();
+        let genesis = Hash::repeat_byte(7);
+
+        let extrinsic = format.sign(&signer, &version(), genesis, 5).unwrap();
+
+        let (extra, additional) = format.encode_extensions(&version(), genesis, 5).unwrap();
+        // Version 4 and signed, an `AccountId32` address, an sr25519 signature.
+        assert_eq!(extrinsic[..2], [0b1000_0100, 0]);
+        assert_eq!(
+            extrinsic[2..34],
+            <[u8; 32]>::from(AccountId32::from(signer.public()))
+        );
+        assert_eq!(extrinsic[34], 1);
+        let signature = sr25519::Signature::try_from(&extrinsic[35..99]).unwrap();
+        assert_eq!(extrinsic[99..], [&extra[..], &format.call].concat());
+        assert!(sr25519::Pair::verify(
+            &signature,
+            [&format.call[..], &extra, &additional].concat(),
+            &signer.public(),
+        ));
+    }
+}
@@ -7,8 +7,13 @@
 
 use std::sync::Arc;
 
-use bloc_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use bloc_runtime::{
+    opaque::{Block, Hash},
+    AccountId, Balance, Nonce,
+};
 
+use futures::channel::mpsc;
+use sc_consensus_manual_seal::EngineCommand;
 pub use sc_rpc::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -26,6 +31,8 @@ pub struct FullDeps<C, P> {
     pub pool: Arc<P>,
     /// Whether to deny unsafe calls
     pub deny_unsafe: DenyUnsafe,
+    /// Channel to the manual seal task of a `--dev` node.
+    pub command_sink: Option<mpsc::Sender<EngineCommand<Hash>>>,
 }
 
 /// Instantiate all RPC extensions.
@@ -45,6 +52,7 @@ where
     P: TransactionPool + Sync + Send + 'static,
 {
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+    use sc_consensus_manual_seal::rpc::{ManualSeal, ManualSealApiServer};
     use substrate_frame_rpc_system::{System, SystemApiServer};
 
     let mut module = RpcExtension::new(());
@@ -52,9 +60,13 @@ where
         client,
         pool,
         deny_unsafe,
+        command_sink,
     } = deps;
 
     module.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
     module.merge(TransactionPayment::new(client).into_rpc())?;
+    if let Some(command_sink) = command_sink {
+        module.merge(ManualSeal::new(command_sink).into_rpc())?;
+    }
     Ok(module)
 }
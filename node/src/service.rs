@@ -3,11 +3,19 @@
 // std
 use std::{sync::Arc, time::Duration};
 
+use codec::{Decode, Encode};
 use cumulus_client_cli::CollatorOptions;
+use futures::{channel::mpsc, stream, StreamExt};
 // Local Runtime Types
 use bloc_runtime::{
     apis::RuntimeApi,
     opaque::{Block, Hash},
+    RELAY_CHAIN_SLOT_DURATION_MILLIS,
+};
+
+use crate::{
+    health::{Health, HealthConfig, LastAuthored},
+    on_demand::CollationMode,
 };
 
 // Cumulus Imports
@@ -15,13 +23,14 @@ use cumulus_client_collator::service::CollatorService;
 use cumulus_client_consensus_aura::collators::lookahead::{self as aura, Params as AuraParams};
 use cumulus_client_consensus_common::ParachainBlockImport as TParachainBlockImport;
 use cumulus_client_consensus_proposer::Proposer;
+use cumulus_client_parachain_inherent::{MockValidationDataInherentDataProvider, MockXcmConfig};
 use cumulus_client_service::{
     build_network, build_relay_chain_interface, prepare_node_config, start_relay_chain_tasks,
     BuildNetworkParams, CollatorSybilResistance, DARecoveryProfile, ParachainHostFunctions,
     StartRelayChainTasksParams,
 };
 use cumulus_primitives_core::{
-    relay_chain::{CollatorPair, ValidationCode},
+    relay_chain::{well_known_keys, CollatorPair, HeadData, ValidationCode},
     ParaId,
 };
 use cumulus_relay_chain_interface::{OverseerHandle, RelayChainInterface};
@@ -30,12 +39,21 @@ use cumulus_relay_chain_interface::{OverseerHandle, RelayChainInterface};
 use frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE;
 use sc_client_api::Backend;
 use sc_consensus::ImportQueue;
+use sc_consensus_manual_seal::{
+    consensus::aura::AuraConsensusDataProvider, EngineCommand, ManualSealParams,
+};
 use sc_executor::{HeapAllocStrategy, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY};
-use sc_network::NetworkBlock;
+use sc_network::{NetworkBackend, NetworkBlock};
 use sc_network_sync::SyncingService;
 use sc_service::{Configuration, PartialComponents, TFullBackend, TFullClient, TaskManager};
 use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker, TelemetryWorkerHandle};
-use sc_transaction_pool_api::OffchainTransactionPoolFactory;
+use sc_transaction_pool_api::{OffchainTransactionPoolFactory, TransactionPool};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::{
+    digests::CompatibleDigestItem, sr25519::AuthoritySignature, AuraApi, Slot,
+};
+use sp_core::twox_128;
 use sp_keystore::KeystorePtr;
 use substrate_prometheus_endpoint::Registry;
 
@@ -166,6 +184,9 @@ fn build_import_queue(
     )
 }
 
+/// The relay chain validates a parachain block with 2 seconds of compute.
+const MAX_AUTHORING_MILLIS: u64 = 2000;
+
 #[allow(clippy::too_many_arguments)]
 fn start_consensus(
     client: Arc<ParachainClient>,
@@ -179,11 +200,27 @@ fn start_consensus(
     sync_oracle: Arc<SyncingService<Block>>,
     keystore: KeystorePtr,
     relay_chain_slot_duration: Duration,
+    authoring_duration: Option<Duration>,
     para_id: ParaId,
     collator_key: CollatorPair,
     overseer_handle: OverseerHandle,
     announce_block: Arc<dyn Fn(Hash, Option<Vec<u8>>) + Send + Sync>,
+    collation_mode: CollationMode,
 ) -> Result<(), sc_service::Error> {
+    if let CollationMode::OnDemand(config) = collation_mode {
+        task_manager.spawn_handle().spawn(
+            "on-demand-orders",
+            None,
+            crate::on_demand::order_coretime(
+                client.clone(),
+                transaction_pool.clone(),
+                relay_chain_interface.clone(),
+                para_id,
+                config,
+            ),
+        );
+    }
+
     let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
         task_manager.spawn_handle(),
         client.clone(),
@@ -201,6 +238,31 @@ fn start_consensus(
         client.clone(),
     );
 
+    // Leave half of each slot for announcing and importing the block, but never author for
+    // longer than the relay chain allows for validating it.
+    let slot_duration = client
+        .runtime_api()
+        .slot_duration(client.info().best_hash)
+        .map_err(|e| sc_service::Error::Other(format!("Failed to read the slot duration: {e}")))?
+        .as_duration();
+    let max_authoring_duration = Duration::from_millis(MAX_AUTHORING_MILLIS);
+    let authoring_duration = match authoring_duration {
+        Some(duration)
+            if duration.is_zero()
+                || duration > max_authoring_duration
+                || duration >= slot_duration =>
+        {
+            return Err(sc_service::Error::Other(format!(
+                "The authoring duration must be below the {}ms slot duration and at most {}ms, got {}ms",
+                slot_duration.as_millis(),
+                MAX_AUTHORING_MILLIS,
+                duration.as_millis(),
+            )));
+        }
+        Some(duration) => duration,
+        None => (slot_duration / 2).min(max_authoring_duration),
+    };
+
     let params = AuraParams {
         create_inherent_data_providers: move |_, ()| async move { Ok(()) },
         block_import,
@@ -221,7 +283,7 @@ fn start_consensus(
         relay_chain_slot_duration,
         proposer,
         collator_service,
-        authoring_duration: Duration::from_millis(2000),
+        authoring_duration,
         reinitialize: false,
     };
 
@@ -236,13 +298,49 @@ fn start_consensus(
     Ok(())
 }
 
+/// Derive the relay chain slot duration from the relay chain's current BABE slot and timestamp.
+///
+/// The timestamp of a block in slot `s` lies within `[s * duration, (s + 1) * duration)`, so
+/// dividing it by the slot gives the duration for any slot past the first few. Returns `None`
+/// when the relay chain state can't tell, e.g. at genesis.
+async fn read_relay_chain_slot_duration(
+    relay_chain_interface: &dyn RelayChainInterface,
+) -> Option<Duration> {
+    let at = relay_chain_interface.best_block_hash().await.ok()?;
+    let slot = relay_chain_interface
+        .get_storage_by_key(at, well_known_keys::CURRENT_SLOT)
+        .await
+        .ok()??;
+    let slot = Slot::decode(&mut &slot[..]).ok()?;
+    let now_key = [twox_128(b"Timestamp"), twox_128(b"Now")].concat();
+    let now = relay_chain_interface
+        .get_storage_by_key(at, &now_key)
+        .await
+        .ok()??;
+    let now = u64::decode(&mut &now[..]).ok()?;
+
+    (*slot > 0).then(|| Duration::from_millis(now / *slot))
+}
+
 /// Start a node with the given parachain `Configuration` and relay chain `Configuration`.
+///
+/// The relay chain slot duration is read from the relay chain, and the authoring duration is
+/// derived from the parachain slot duration unless `authoring_duration` is given. Offchain
+/// workers may only make HTTP requests with `offchain_http`. Session keys derived from
+/// `session_seed` are inserted into the keystore before anything else starts. Health checks are
+/// served when `health` is given.
+#[allow(clippy::too_many_arguments)]
 #[sc_tracing::logging::prefix_logs_with("Parachain")]
 pub async fn start_parachain_node(
     parachain_config: Configuration,
     polkadot_config: Configuration,
     collator_options: CollatorOptions,
     para_id: ParaId,
+    collation_mode: CollationMode,
+    authoring_duration: Option<Duration>,
+    offchain_http: bool,
+    session_seed: Option<String>,
+    health: Option<HealthConfig>,
     hwbench: Option<sc_sysinfo::HwBench>,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient>)> {
     let parachain_config = prepare_node_config(parachain_config);
@@ -259,6 +357,15 @@ pub async fn start_parachain_node(
     let backend = params.backend.clone();
     let mut task_manager = params.task_manager;
 
+    if let Some(seed) = session_seed {
+        crate::session_keys::generate_session_keys(
+            &*client,
+            params.keystore_container.keystore(),
+            Some(seed),
+        )
+        .map_err(sc_service::Error::Other)?;
+    }
+
     let (relay_chain_interface, collator_key) = build_relay_chain_interface(
         polkadot_config,
         &parachain_config,
@@ -306,7 +413,7 @@ pub async fn start_parachain_node(
                 )),
                 network_provider: Arc::new(network.clone()),
                 is_validator: parachain_config.role.is_authority(),
-                enable_http_requests: false,
+                enable_http_requests: offchain_http,
                 custom_extensions: move |_| vec![],
             })
             .run(client.clone(), task_manager.spawn_handle())
@@ -323,6 +430,7 @@ pub async fn start_parachain_node(
                 client: client.clone(),
                 pool: transaction_pool.clone(),
                 deny_unsafe,
+                command_sink: None,
             };
 
             crate::rpc::create_full(deps).map_err(Into::into)
@@ -374,7 +482,30 @@ pub async fn start_parachain_node(
         Arc::new(move |hash, data| sync_service.announce_block(hash, data))
     };
 
-    let relay_chain_slot_duration = Duration::from_secs(6);
+    // The runtime derives its slots from relay chain slots of `RELAY_CHAIN_SLOT_DURATION_MILLIS`,
+    // a collator on a relay chain with other slots would only author blocks the runtime rejects.
+    let runtime_relay_chain_slot_duration =
+        Duration::from_millis(RELAY_CHAIN_SLOT_DURATION_MILLIS.into());
+    let relay_chain_slot_duration = read_relay_chain_slot_duration(&*relay_chain_interface)
+        .await
+        .unwrap_or_else(|| {
+            log::info!(
+                "Could not read the relay chain slot duration, assuming {}ms",
+                RELAY_CHAIN_SLOT_DURATION_MILLIS
+            );
+            runtime_relay_chain_slot_duration
+        });
+    if relay_chain_slot_duration != runtime_relay_chain_slot_duration {
+        let message = format!(
+            "The relay chain slot duration is {}ms, but the runtime is built for {}ms",
+            relay_chain_slot_duration.as_millis(),
+            RELAY_CHAIN_SLOT_DURATION_MILLIS
+        );
+        if validator {
+            return Err(sc_service::Error::Other(message));
+        }
+        log::warn!("{message}");
+    }
 
     let overseer_handle = relay_chain_interface
         .overseer_handle()
@@ -397,7 +528,45 @@ pub async fn start_parachain_node(
         sync_service: sync_service.clone(),
     })?;
 
+    let last_authored = LastAuthored::default();
+    if let Some(health) = health {
+        let server = crate::health::serve(
+            health.addr,
+            Health {
+                client: client.clone(),
+                keystore: params.keystore_container.keystore(),
+                sync_service: sync_service.clone(),
+                relay_chain_interface: relay_chain_interface.clone(),
+                collator: validator,
+                last_authored: last_authored.clone(),
+                max_authoring_gap: health.max_authoring_gap,
+            },
+        )
+        .map_err(sc_service::Error::Other)?;
+        task_manager
+            .spawn_handle()
+            .spawn("health-endpoint", None, server);
+    }
+
     if validator {
+        crate::session_keys::check_aura_key(&*client, &params.keystore_container.keystore())
+            .map_err(sc_service::Error::Other)?;
+        let collator_key = collator_key.ok_or_else(|| {
+            sc_service::Error::Other("The relay chain interface provided no collator key".into())
+        })?;
+
+        // The collator announces every block it authors.
+        let announce_block = Arc::new(move |hash: Hash, data: Option<Vec<u8>>| {
+            last_authored.note();
+            announce_block(hash, data)
+        });
+
+        task_manager.spawn_handle().spawn(
+            "aura-equivocation-reporter",
+            None,
+            crate::equivocation::report_equivocations(client.clone(), transaction_pool.clone()),
+        );
+
         start_consensus(
             client.clone(),
             backend,
@@ -410,10 +579,12 @@ pub async fn start_parachain_node(
             sync_service.clone(),
             params.keystore_container.keystore(),
             relay_chain_slot_duration,
+            authoring_duration,
             para_id,
-            collator_key.expect("Command line arguments do not allow this. qed"),
+            collator_key,
             overseer_handle,
             announce_block,
+            collation_mode,
         )?;
     }
 
@@ -421,3 +592,185 @@ pub async fn start_parachain_node(
 
     Ok((task_manager, client))
 }
+
+/// How a standalone development node seals its blocks.
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Sealing {
+    /// Seal a block as soon as a transaction enters the pool.
+    Instant,
+    /// Seal blocks only when asked to through the `engine_createBlock` RPC.
+    Manual,
+}
+
+/// Start a standalone development node that seals its own blocks, without a relay chain.
+///
+/// The validation data of each block is mocked. The first block takes its relay chain slot from
+/// the wall clock and every later block the slot right after its parent's, so that Aura and the
+/// consensus hook accept it however quickly blocks are sealed, and no collator's slot is ever
+/// skipped and counted as missed.
+pub fn start_dev_node(
+    config: Configuration,
+    para_id: ParaId,
+    sealing: Sealing,
+) -> sc_service::error::Result<TaskManager> {
+    let params = new_partial(&config)?;
+    let (_, mut telemetry, _) = params.other;
+    let net_config = sc_network::config::FullNetworkConfiguration::<
+        _,
+        _,
+        sc_network::NetworkWorker<Block, Hash>,
+    >::new(&config.network);
+    let metrics = sc_network::NetworkWorker::<Block, Hash>::register_notification_metrics(
+        config.prometheus_registry(),
+    );
+
+    let client = params.client.clone();
+    let backend = params.backend.clone();
+    let mut task_manager = params.task_manager;
+    let transaction_pool = params.transaction_pool.clone();
+    let prometheus_registry = config.prometheus_registry().cloned();
+
+    let (network, system_rpc_tx, tx_handler_controller, start_network, sync_service) =
+        sc_service::build_network(sc_service::BuildNetworkParams {
+            config: &config,
+            net_config,
+            client: client.clone(),
+            transaction_pool: transaction_pool.clone(),
+            spawn_handle: task_manager.spawn_handle(),
+            import_queue: params.import_queue,
+            block_announce_validator_builder: None,
+            warp_sync_params: None,
+            block_relay: None,
+            metrics,
+        })?;
+
+    let (command_sink, rpc_commands) = mpsc::channel(1024);
+    let commands_stream = match sealing {
+        Sealing::Instant => stream::select(
+            rpc_commands,
+            transaction_pool
+                .import_notification_stream()
+                .map(|_| EngineCommand::SealNewBlock {
+                    create_empty: true,
+                    finalize: true,
+                    parent_hash: None,
+                    sender: None,
+                }),
+        )
+        .boxed(),
+        Sealing::Manual => rpc_commands.boxed(),
+    };
+
+    let rpc_builder = {
+        let client = client.clone();
+        let transaction_pool = transaction_pool.clone();
+
+        Box::new(move |deny_unsafe, _| {
+            let deps = crate::rpc::FullDeps {
+                client: client.clone(),
+                pool: transaction_pool.clone(),
+                deny_unsafe,
+                command_sink: Some(command_sink.clone()),
+            };
+
+            crate::rpc::create_full(deps).map_err(Into::into)
+        })
+    };
+
+    sc_service::spawn_tasks(sc_service::SpawnTasksParams {
+        rpc_builder,
+        client: client.clone(),
+        transaction_pool: transaction_pool.clone(),
+        task_manager: &mut task_manager,
+        config,
+        keystore: params.keystore_container.keystore(),
+        backend: backend.clone(),
+        network,
+        sync_service,
+        system_rpc_tx,
+        tx_handler_controller,
+        telemetry: telemetry.as_mut(),
+    })?;
+
+    let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+        task_manager.spawn_handle(),
+        client.clone(),
+        transaction_pool.clone(),
+        prometheus_registry.as_ref(),
+        telemetry.as_ref().map(|t| t.handle()),
+    );
+
+    let create_inherent_data_providers = {
+        let client = client.clone();
+
+        move |parent: Hash, ()| {
+            let client = client.clone();
+
+            async move {
+                let parent_header = client.header(parent)?.ok_or("Parent header not found")?;
+                let para_slot_millis = client.runtime_api().slot_duration(parent)?.as_millis();
+                let relay_slot_millis = u64::from(RELAY_CHAIN_SLOT_DURATION_MILLIS);
+                let parent_slot = parent_header
+                    .digest
+                    .logs()
+                    .iter()
+                    .find_map(CompatibleDigestItem::<AuthoritySignature>::as_aura_pre_digest)
+                    .map_or(0, u64::from);
+
+                // The consensus hook expects the parachain slot to follow from the relay chain
+                // slot, so pick the relay chain slot first and derive the timestamp from it.
+                let relay_slot = if parent_slot == 0 {
+                    sp_timestamp::Timestamp::current().as_millis() / relay_slot_millis
+                } else {
+                    ((parent_slot + 1) * para_slot_millis).div_ceil(relay_slot_millis)
+                };
+                let para_slot = relay_slot * relay_slot_millis / para_slot_millis;
+                let timestamp =
+                    sp_timestamp::InherentDataProvider::new((para_slot * para_slot_millis).into());
+
+                let validation_data = MockValidationDataInherentDataProvider {
+                    current_para_block: parent_header.number + 1,
+                    relay_offset: 1000,
+                    relay_blocks_per_para_block: 1,
+                    para_blocks_per_relay_epoch: 10,
+                    relay_randomness_config: (),
+                    xcm_config: MockXcmConfig::new(&*client, parent, para_id, Default::default()),
+                    raw_downward_messages: vec![],
+                    raw_horizontal_messages: vec![],
+                    // The parent is always included, so the unincluded segment never fills up.
+                    additional_key_values: Some(vec![
+                        (
+                            well_known_keys::CURRENT_SLOT.to_vec(),
+                            Slot::from(relay_slot).encode(),
+                        ),
+                        (
+                            well_known_keys::para_head(para_id),
+                            HeadData(parent_header.encode()).encode(),
+                        ),
+                    ]),
+                };
+
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>((timestamp, validation_data))
+            }
+        }
+    };
+
+    task_manager.spawn_essential_handle().spawn_blocking(
+        "manual-seal",
+        None,
+        sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+            block_import: client.clone(),
+            env: proposer_factory,
+            client: client.clone(),
+            pool: transaction_pool,
+            commands_stream,
+            select_chain: sc_consensus::LongestChain::new(backend),
+            consensus_data_provider: Some(Box::new(AuraConsensusDataProvider::new(client))),
+            create_inherent_data_providers,
+        }),
+    );
+
+    start_network.start_network();
+
+    Ok(task_manager)
+}
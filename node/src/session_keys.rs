@@ -0,0 +1,123 @@
+//! Session key management for collators.
+
+use std::sync::Arc;
+
+use bloc_runtime::{opaque::Block, AuraId, RuntimeCall, SessionKeys};
+use codec::{Decode, Encode};
+use sc_cli::{CliConfiguration, KeystoreParams, SharedParams};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_consensus_aura::AuraApi;
+use sp_core::{
+    bytes::to_hex,
+    crypto::{key_types, Ss58Codec},
+};
+use sp_keystore::{KeystoreExt, KeystorePtr};
+
+/// Generate a new set of session keys into the node's keystore.
+///
+/// Prints the SCALE-encoded session keys together with the `session.set_keys` call that
+/// registers them, ready to be submitted from the collator's account.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct SessionKeysCmd {
+    #[allow(missing_docs)]
+    #[command(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[command(flatten)]
+    pub keystore_params: KeystoreParams,
+}
+
+impl SessionKeysCmd {
+    /// Run the session-keys command.
+    pub fn run<C>(&self, client: Arc<C>, keystore: KeystorePtr) -> sc_cli::Result<()>
+    where
+        C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+        C::Api: sp_session::SessionKeys<Block>,
+    {
+        let keys = generate_session_keys(&*client, keystore, None)?;
+        let call = RuntimeCall::Session(pallet_session::Call::set_keys {
+            keys: keys.clone(),
+            proof: vec![],
+        });
+
+        println!("Aura key:         {}", keys.aura.to_ss58check());
+        println!("Session keys:     {}", to_hex(&keys.encode(), false));
+        println!("session.set_keys: {}", to_hex(&call.encode(), false));
+        Ok(())
+    }
+}
+
+/// Generate a key for every key type in the runtime's `SessionKeys` into `keystore`.
+///
+/// The keys are derived from `seed` when given, a secret URI, and random otherwise.
+pub fn generate_session_keys<C>(
+    client: &C,
+    keystore: KeystorePtr,
+    seed: Option<String>,
+) -> Result<SessionKeys, String>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: sp_session::SessionKeys<Block>,
+{
+    use sp_session::SessionKeys as _;
+
+    let mut runtime_api = client.runtime_api();
+    runtime_api.register_extension(KeystoreExt(keystore));
+    let keys = runtime_api
+        .generate_session_keys(client.info().best_hash, seed.map(String::into_bytes))
+        .map_err(|e| format!("Failed to generate session keys: {e}"))?;
+
+    SessionKeys::decode(&mut &keys[..]).map_err(|e| format!("Invalid session keys: {e}"))
+}
+
+/// Make sure `keystore` holds an Aura key to author blocks with.
+///
+/// A key outside the current authority set is only warned about, the collator may not have been
+/// elected yet.
+pub fn check_aura_key<C>(client: &C, keystore: &KeystorePtr) -> Result<(), String>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: AuraApi<Block, AuraId>,
+{
+    if keystore.sr25519_public_keys(key_types::AURA).is_empty() {
+        return Err(
+            "No Aura key in the keystore. Provide one with `--session-key-file`, \
+            `BLOC_SESSION_SEED` or `bloc-node session-keys`."
+                .into(),
+        );
+    }
+
+    if !has_authority_key(client, keystore)? {
+        log::warn!("None of the Aura keys in the keystore is in the current authority set");
+    }
+    Ok(())
+}
+
+/// Whether `keystore` holds a key of the current Aura authority set.
+pub fn has_authority_key<C>(client: &C, keystore: &KeystorePtr) -> Result<bool, String>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+    C::Api: AuraApi<Block, AuraId>,
+{
+    let authorities = client
+        .runtime_api()
+        .authorities(client.info().best_hash)
+        .map_err(|e| format!("Failed to read the Aura authorities: {e}"))?;
+
+    Ok(keystore
+        .sr25519_public_keys(key_types::AURA)
+        .into_iter()
+        .any(|key| authorities.contains(&AuraId::from(key))))
+}
+
+impl CliConfiguration for SessionKeysCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn keystore_params(&self) -> Option<&KeystoreParams> {
+        Some(&self.keystore_params)
+    }
+}
@@ -0,0 +1,144 @@
+//! Benchmarking setup for pallet-collator-offences
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as CollatorOffences;
+use frame_benchmarking::{account, v2::*};
+use frame_system::{pallet_prelude::HeaderFor, EventRecord, RawOrigin};
+use sp_consensus_aura::{digests::CompatibleDigestItem, Slot};
+use sp_runtime::{
+    traits::{Hash, Header},
+    DigestItem, RuntimeAppPublic,
+};
+use sp_std::prelude::*;
+
+const SEED: u32 = 0;
+
+/// Number of collators in the session. Up to one less than that many slots can be missed at once.
+const COLLATORS: u32 = 100;
+
+fn assert_last_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
+    let events = frame_system::Pallet::<T>::events();
+    let system_event: <T as frame_system::Config>::RuntimeEvent = generic_event.into();
+    let EventRecord { event, .. } = &events[events.len() - 1];
+    assert_eq!(event, &system_event);
+}
+
+/// Make `COLLATORS` accounts the collators of the current session, none of them disabled.
+fn set_collators<T: Config>() -> Vec<T::ValidatorId> {
+    let collators = (0..COLLATORS)
+        .map(|c| account::<T::ValidatorId>("collator", c, SEED))
+        .collect::<Vec<_>>();
+    pallet_session::Validators::<T>::put(&collators);
+    pallet_session::DisabledValidators::<T>::kill();
+    collators
+}
+
+/// A header sealed by `key` in `slot`, made unique by `extrinsics_root`.
+fn sealed_header<T: Config>(
+    key: &T::AuthorityId,
+    slot: Slot,
+    extrinsics_root: &[u8],
+) -> HeaderFor<T> {
+    type Signature<T> = <<T as pallet_aura::Config>::AuthorityId as RuntimeAppPublic>::Signature;
+
+    let mut header = HeaderFor::<T>::new(
+        1u32.into(),
+        T::Hashing::hash(extrinsics_root),
+        Default::default(),
+        Default::default(),
+        Default::default(),
+    );
+    header
+        .digest_mut()
+        .push(<DigestItem as CompatibleDigestItem<Signature<T>>>::aura_pre_digest(slot));
+    let signature = key
+        .sign(&header.hash())
+        .expect("the key was generated in the keystore");
+    header
+        .digest_mut()
+        .push(<DigestItem as CompatibleDigestItem<Signature<T>>>::aura_seal(signature));
+    header
+}
+
+#[benchmarks]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn report_equivocation() {
+        let offender = set_collators::<T>().remove(0);
+        T::BenchmarkHelper::setup_collator(&offender);
+        let key = T::AuthorityId::generate_pair(None);
+        pallet_session::KeyOwner::<T>::insert(
+            (T::AuthorityId::ID, key.to_raw_vec()),
+            offender.clone(),
+        );
+
+        let slot = Slot::from(1000);
+        pallet_aura::CurrentSlot::<T>::put(slot);
+        let proof = EquivocationProof::<T> {
+            offender: key.clone(),
+            slot,
+            first_header: sealed_header::<T>(&key, slot, b"first"),
+            second_header: sealed_header::<T>(&key, slot, b"second"),
+        };
+
+        #[extrinsic_call]
+        report_equivocation_unsigned(RawOrigin::None, Box::new(proof));
+
+        assert_last_event::<T>(
+            Event::CollatorPunished {
+                collator: offender,
+                offence: Offence::Equivocation,
+                disabled: true,
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn note_missed_slots(s: Linear<1, { COLLATORS - 1 }>) {
+        T::BenchmarkHelper::track_missed_slots();
+        set_collators::<T>();
+        TrackedSession::<T>::put(pallet_session::CurrentIndex::<T>::get());
+        LastSlot::<T>::put(Slot::from(1000));
+        pallet_aura::CurrentSlot::<T>::put(Slot::from(1000 + u64::from(s) + 1));
+
+        #[block]
+        {
+            CollatorOffences::<T>::note_missed_slots();
+        }
+
+        assert_eq!(MissedSlots::<T>::iter().count(), s as usize);
+    }
+
+    #[benchmark]
+    fn punish() {
+        let collator = set_collators::<T>().remove(0);
+        T::BenchmarkHelper::setup_collator(&collator);
+
+        #[block]
+        {
+            CollatorOffences::<T>::punish(&collator, Offence::MissedSlots);
+        }
+
+        assert_last_event::<T>(
+            Event::CollatorPunished {
+                collator,
+                offence: Offence::MissedSlots,
+                disabled: true,
+            }
+            .into(),
+        );
+    }
+
+    impl_benchmark_test_suite!(
+        CollatorOffences,
+        crate::mock::new_bench_ext(),
+        crate::mock::Test,
+    );
+}
@@ -0,0 +1,374 @@
+//! # Collator Offences
+//!
+//! Detects collator misbehaviour and punishes it.
+//!
+//! Two offences are recognised:
+//!
+//! - **Equivocation**: authoring two different blocks of the same height in the same Aura slot,
+//!   on the same relay parent. Nodes that import both blocks submit the two headers as an
+//!   [`EquivocationProof`] through [`Pallet::report_equivocation_unsigned`]. Blocks built on
+//!   different relay parents are not an equivocation: collators build on every relay chain fork,
+//!   and relay forks can share a slot.
+//! - **Missed slots**: the chain moving past a collator's Aura slot without a block from them.
+//!   Missed slots are counted from the slot gaps between consecutive blocks, and a collator that
+//!   misses [`Config::MaxMissedSlots`] slots in a session is punished once for that session.
+//!   Chains whose blocks don't fill every slot, e.g. ones buying on-demand coretime, set
+//!   `MaxMissedSlots` to zero to not track missed slots at all.
+//!
+//! A punished collator is slashed through [`Config::Slash`] and disabled in `pallet_session`,
+//! which takes them out of the Aura rotation until the next session. At most a third of the
+//! collators can be disabled at once, so that the chain keeps producing blocks.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+pub use weights::WeightInfo;
+
+use sp_runtime::Perbill;
+
+/// Punishes a collator's stake for an offence.
+pub trait SlashCollator<ValidatorId> {
+    /// Slash `fraction` of `who`'s stake.
+    fn slash(who: &ValidatorId, fraction: Perbill);
+}
+
+impl<ValidatorId> SlashCollator<ValidatorId> for () {
+    fn slash(_: &ValidatorId, _: Perbill) {}
+}
+
+/// Prepares the runtime for the benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<ValidatorId> {
+    /// Give `who` the most expensive stake for [`Config::Slash`] to slash.
+    fn setup_collator(who: &ValidatorId);
+
+    /// Make [`Config::MaxMissedSlots`] non-zero, if it isn't, so that missed slots are tracked.
+    fn track_missed_slots();
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl<ValidatorId> BenchmarkHelper<ValidatorId> for () {
+    fn setup_collator(_: &ValidatorId) {}
+
+    fn track_missed_slots() {}
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::{SlashCollator, WeightInfo};
+    use codec::{Decode, Encode, MaxEncodedLen};
+    use cumulus_primitives_core::rpsr_digest::extract_relay_parent_storage_root;
+    use frame_support::{dispatch::DispatchClass, pallet_prelude::*};
+    use frame_system::pallet_prelude::*;
+    use scale_info::TypeInfo;
+    use sp_consensus_aura::{digests::CompatibleDigestItem, Slot};
+    use sp_runtime::{
+        traits::{Header as HeaderT, Saturating},
+        DigestItem, Perbill, RuntimeAppPublic, RuntimeDebug,
+    };
+    use sp_staking::SessionIndex;
+    use sp_std::prelude::*;
+
+    pub(crate) const LOG_TARGET: &str = "runtime::collator-offences";
+
+    /// Two headers that the same Aura authority sealed for the same slot and relay parent.
+    pub type EquivocationProof<T> = sp_consensus_slots::EquivocationProof<
+        HeaderFor<T>,
+        <T as pallet_aura::Config>::AuthorityId,
+    >;
+
+    /// A punishable offence.
+    #[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub enum Offence {
+        /// Sealed two different blocks of the same height in the same slot.
+        Equivocation,
+        /// Missed `MaxMissedSlots` slots in a session.
+        MissedSlots,
+    }
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config + pallet_aura::Config + pallet_session::Config {
+        /// Overarching event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// Slashes punished collators.
+        type Slash: SlashCollator<Self::ValidatorId>;
+
+        /// Share of the stake slashed for an equivocation.
+        type EquivocationSlash: Get<Perbill>;
+
+        /// Number of missed slots in a session after which a collator is punished. Zero disables
+        /// the tracking of missed slots.
+        type MaxMissedSlots: Get<u32>;
+
+        /// Share of the stake slashed for missing `MaxMissedSlots` slots.
+        type MissedSlotsSlash: Get<Perbill>;
+
+        /// Number of slots after which an equivocation can no longer be reported.
+        #[pallet::constant]
+        type ReportLongevity: Get<u64>;
+
+        /// The weight information of this pallet.
+        type WeightInfo: WeightInfo;
+
+        /// Sets up the collators punished in the benchmarks.
+        #[cfg(feature = "runtime-benchmarks")]
+        type BenchmarkHelper: super::BenchmarkHelper<Self::ValidatorId>;
+    }
+
+    /// Slots missed by each collator in the current session.
+    #[pallet::storage]
+    pub type MissedSlots<T: Config> = StorageMap<_, Twox64Concat, T::ValidatorId, u32, ValueQuery>;
+
+    /// The session `MissedSlots` is counted for.
+    #[pallet::storage]
+    pub type TrackedSession<T: Config> = StorageValue<_, SessionIndex, ValueQuery>;
+
+    /// The slot of the previous block.
+    #[pallet::storage]
+    pub type LastSlot<T: Config> = StorageValue<_, Slot, ValueQuery>;
+
+    /// The slot of the latest equivocation reported for each collator.
+    #[pallet::storage]
+    pub type LastEquivocation<T: Config> =
+        StorageMap<_, Twox64Concat, T::ValidatorId, Slot, OptionQuery>;
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A collator was punished for an offence.
+        CollatorPunished {
+            collator: T::ValidatorId,
+            offence: Offence,
+            /// Whether the collator was disabled for the rest of the session.
+            disabled: bool,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The headers are not an equivocation by the offender.
+        InvalidEquivocationProof,
+        /// The equivocation is older than `ReportLongevity` slots.
+        StaleEquivocationProof,
+        /// The offender's key does not belong to any collator.
+        UnknownOffender,
+        /// An equivocation at this or a later slot was already reported for the offender.
+        DuplicateEquivocationReport,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_initialize(_: BlockNumberFor<T>) -> Weight {
+            Self::note_missed_slots()
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Report an Aura equivocation.
+        ///
+        /// Only block authors can include this, and only if it came from their own node. The
+        /// offender is slashed `EquivocationSlash` of their stake and disabled.
+        #[pallet::call_index(0)]
+        #[pallet::weight((<T as Config>::WeightInfo::report_equivocation(), DispatchClass::Operational))]
+        pub fn report_equivocation_unsigned(
+            origin: OriginFor<T>,
+            proof: Box<EquivocationProof<T>>,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+
+            let offender = Self::check_equivocation(&proof)?;
+            LastEquivocation::<T>::insert(&offender, proof.slot);
+            Self::punish(&offender, Offence::Equivocation);
+            Ok(())
+        }
+    }
+
+    #[pallet::validate_unsigned]
+    impl<T: Config> ValidateUnsigned for Pallet<T> {
+        type Call = Call<T>;
+
+        fn validate_unsigned(source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+            let Call::report_equivocation_unsigned { proof } = call else {
+                return InvalidTransaction::Call.into();
+            };
+
+            // Reports are submitted by the node of a block author, do not gossip them.
+            if !matches!(
+                source,
+                TransactionSource::Local | TransactionSource::InBlock
+            ) {
+                return InvalidTransaction::Call.into();
+            }
+
+            let offender = Self::check_equivocation(proof).map_err(|e| {
+                log::debug!(
+                    target: LOG_TARGET,
+                    "rejecting equivocation report: {:?}",
+                    e,
+                );
+                InvalidTransaction::BadProof
+            })?;
+
+            ValidTransaction::with_tag_prefix("CollatorEquivocation")
+                .priority(TransactionPriority::MAX)
+                .and_provides((offender, proof.slot))
+                .longevity(T::ReportLongevity::get())
+                .propagate(false)
+                .build()
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Check that `proof` is a recent, unreported equivocation and return the offender.
+        pub fn check_equivocation(
+            proof: &EquivocationProof<T>,
+        ) -> Result<T::ValidatorId, Error<T>> {
+            let first = &proof.first_header;
+            let second = &proof.second_header;
+            ensure!(
+                first.hash() != second.hash() && first.number() == second.number(),
+                Error::<T>::InvalidEquivocationProof
+            );
+            ensure!(
+                extract_relay_parent_storage_root(first.digest())
+                    == extract_relay_parent_storage_root(second.digest()),
+                Error::<T>::InvalidEquivocationProof
+            );
+            for header in [first, second] {
+                ensure!(
+                    Self::sealed_slot(header.clone(), &proof.offender) == Some(proof.slot),
+                    Error::<T>::InvalidEquivocationProof
+                );
+            }
+
+            let current = u64::from(pallet_aura::CurrentSlot::<T>::get());
+            ensure!(
+                current.saturating_sub(u64::from(proof.slot)) <= T::ReportLongevity::get(),
+                Error::<T>::StaleEquivocationProof
+            );
+
+            let offender = pallet_session::Pallet::<T>::key_owner(
+                <T::AuthorityId as RuntimeAppPublic>::ID,
+                &proof.offender.to_raw_vec(),
+            )
+            .ok_or(Error::<T>::UnknownOffender)?;
+            // `None` orders before any slot.
+            ensure!(
+                LastEquivocation::<T>::get(&offender) < Some(proof.slot),
+                Error::<T>::DuplicateEquivocationReport
+            );
+            Ok(offender)
+        }
+
+        /// The slot of `header` if it is sealed by `author`.
+        fn sealed_slot(mut header: HeaderFor<T>, author: &T::AuthorityId) -> Option<Slot> {
+            type Signature<T> =
+                <<T as pallet_aura::Config>::AuthorityId as RuntimeAppPublic>::Signature;
+
+            let seal = header.digest_mut().pop()?;
+            let signature =
+                <DigestItem as CompatibleDigestItem<Signature<T>>>::as_aura_seal(&seal)?;
+            let slot = header.digest().logs().iter().find_map(|log| {
+                <DigestItem as CompatibleDigestItem<Signature<T>>>::as_aura_pre_digest(log)
+            })?;
+
+            // The seal signs the hash of the header without the seal.
+            author.verify(&header.hash(), &signature).then_some(slot)
+        }
+
+        /// Count the slots skipped since the previous block against their authors.
+        pub(crate) fn note_missed_slots() -> Weight {
+            let mut weight = T::DbWeight::get().reads_writes(4, 1);
+
+            let session = pallet_session::CurrentIndex::<T>::get();
+            let slot = pallet_aura::CurrentSlot::<T>::get();
+            let last = LastSlot::<T>::mutate(|last| sp_std::mem::replace(last, slot));
+
+            if TrackedSession::<T>::get() != session {
+                // The skipped slots may belong to the previous collator set, start afresh.
+                let _ = MissedSlots::<T>::clear(u32::MAX, None);
+                TrackedSession::<T>::put(session);
+                return weight.saturating_add(T::DbWeight::get().writes(2));
+            }
+
+            let max_missed = T::MaxMissedSlots::get();
+            if max_missed == 0 {
+                return weight;
+            }
+
+            let validators = pallet_session::Validators::<T>::get();
+            let authorities = validators.len() as u64;
+            let (last, slot) = (u64::from(last), u64::from(slot));
+            let skipped = slot.saturating_sub(last).saturating_sub(1);
+            // Skipping every collator's slot means the chain stalled, not that anyone misbehaved.
+            if last == 0 || skipped == 0 || skipped >= authorities {
+                return weight;
+            }
+
+            let disabled = pallet_session::DisabledValidators::<T>::get();
+            for missed in (last + 1)..slot {
+                let index = (missed % authorities) as u32;
+                if disabled.binary_search(&index).is_ok() {
+                    continue;
+                }
+                let collator = &validators[index as usize];
+                let count = MissedSlots::<T>::mutate(collator, |count| {
+                    count.saturating_inc();
+                    *count
+                });
+                if count == max_missed {
+                    Self::punish(collator, Offence::MissedSlots);
+                    weight.saturating_accrue(<T as Config>::WeightInfo::punish());
+                }
+            }
+            weight.saturating_add(<T as Config>::WeightInfo::note_missed_slots(skipped as u32))
+        }
+
+        /// Slash `collator` for `offence` and disable them until the next session.
+        pub(crate) fn punish(collator: &T::ValidatorId, offence: Offence) {
+            let fraction = match offence {
+                Offence::Equivocation => T::EquivocationSlash::get(),
+                Offence::MissedSlots => T::MissedSlotsSlash::get(),
+            };
+            T::Slash::slash(collator, fraction);
+            let disabled = Self::disable(collator);
+
+            log::info!(
+                target: LOG_TARGET,
+                "punished {:?} for {:?}, disabled: {}",
+                collator,
+                offence,
+                disabled,
+            );
+            Self::deposit_event(Event::CollatorPunished {
+                collator: collator.clone(),
+                offence,
+                disabled,
+            });
+        }
+
+        /// Disable `collator` unless a third of the collators already are.
+        fn disable(collator: &T::ValidatorId) -> bool {
+            let collators = pallet_session::Validators::<T>::decode_len().unwrap_or_default();
+            let disabled =
+                pallet_session::DisabledValidators::<T>::decode_len().unwrap_or_default();
+            if disabled >= collators.saturating_sub(1) / 3 {
+                return false;
+            }
+            pallet_session::Pallet::<T>::disable(collator)
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use super::*;
+use crate as collator_offences;
+use cumulus_primitives_core::rpsr_digest::relay_parent_storage_root_item;
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{ConstBool, ConstU32, ConstU64, Hooks},
+};
+use sp_consensus_aura::{digests::CompatibleDigestItem, Slot};
+use sp_runtime::{
+    testing::{TestSignature, UintAuthorityId, H256},
+    traits::{BlakeTwo256, ConvertInto, Header as _, OpaqueKeys},
+    BuildStorage, DigestItem, RuntimeAppPublic,
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+type Header = sp_runtime::generic::Header<u64, BlakeTwo256>;
+
+/// The relay parent storage root of the headers in [`equivocation`].
+pub const RELAY_PARENT: H256 = H256::repeat_byte(0xaa);
+
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        Timestamp: pallet_timestamp,
+        Session: pallet_session,
+        Aura: pallet_aura,
+        CollatorOffences: collator_offences,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+}
+
+impl pallet_timestamp::Config for Test {
+    type Moment = u64;
+    type OnTimestampSet = Aura;
+    type MinimumPeriod = ConstU64<1>;
+    type WeightInfo = ();
+}
+
+impl pallet_aura::Config for Test {
+    type AuthorityId = UintAuthorityId;
+    type DisabledValidators = Session;
+    type MaxAuthorities = ConstU32<10>;
+    type AllowMultipleBlocksPerSlot = ConstBool<false>;
+    type SlotDuration = ConstU64<2>;
+}
+
+sp_runtime::impl_opaque_keys! {
+    pub struct MockSessionKeys {
+        pub aura: UintAuthorityId,
+    }
+}
+
+pub struct TestSessionHandler;
+impl pallet_session::SessionHandler<u64> for TestSessionHandler {
+    const KEY_TYPE_IDS: &'static [sp_runtime::KeyTypeId] = &[UintAuthorityId::ID];
+    fn on_genesis_session<Ks: OpaqueKeys>(_: &[(u64, Ks)]) {}
+    fn on_new_session<Ks: OpaqueKeys>(_: bool, _: &[(u64, Ks)], _: &[(u64, Ks)]) {}
+    fn on_before_session_ending() {}
+    fn on_disabled(_: u32) {}
+}
+
+impl pallet_session::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type ValidatorId = <Self as frame_system::Config>::AccountId;
+    type ValidatorIdOf = ConvertInto;
+    type ShouldEndSession = pallet_session::PeriodicSessions<ConstU64<1_000>, ConstU64<0>>;
+    type NextSessionRotation = pallet_session::PeriodicSessions<ConstU64<1_000>, ConstU64<0>>;
+    type SessionManager = ();
+    type SessionHandler = TestSessionHandler;
+    type Keys = MockSessionKeys;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    /// Every slash, in order.
+    pub static Slashes: Vec<(u64, Perbill)> = vec![];
+    pub static MaxMissedSlots: u32 = 3;
+}
+
+pub struct RecordSlash;
+impl SlashCollator<u64> for RecordSlash {
+    fn slash(who: &u64, fraction: Perbill) {
+        Slashes::mutate(|slashes| slashes.push((*who, fraction)));
+    }
+}
+
+/// Number of slots an equivocation can be reported for.
+pub const REPORT_LONGEVITY: u64 = 10;
+
+impl Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Slash = RecordSlash;
+    type EquivocationSlash = EquivocationSlash;
+    type MaxMissedSlots = MaxMissedSlots;
+    type MissedSlotsSlash = MissedSlotsSlash;
+    type ReportLongevity = ConstU64<REPORT_LONGEVITY>;
+    type WeightInfo = ();
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = ();
+}
+
+parameter_types! {
+    pub const EquivocationSlash: Perbill = Perbill::from_percent(10);
+    pub const MissedSlotsSlash: Perbill = Perbill::from_percent(1);
+}
+
+/// The collators, with the Aura key of each matching their account.
+pub const COLLATORS: [u64; 7] = [1, 2, 3, 4, 5, 6, 7];
+
+/// Externalities with `COLLATORS` as the session's collators.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    build_ext(&COLLATORS)
+}
+
+/// Externalities without collators, for the benchmarks to set up their own.
+#[cfg(feature = "runtime-benchmarks")]
+pub fn new_bench_ext() -> sp_io::TestExternalities {
+    build_ext(&[])
+}
+
+fn build_ext(collators: &[u64]) -> sp_io::TestExternalities {
+    Slashes::take();
+    MaxMissedSlots::set(3);
+    let mut t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+    pallet_session::GenesisConfig::<Test> {
+        keys: collators
+            .iter()
+            .map(|&who| {
+                (
+                    who,
+                    who,
+                    MockSessionKeys {
+                        aura: UintAuthorityId(who),
+                    },
+                )
+            })
+            .collect(),
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Import a block authored in `slot`.
+pub fn block_in_slot(slot: u64) {
+    let number = System::block_number() + 1;
+    System::set_block_number(number);
+    pallet_aura::CurrentSlot::<Test>::put(Slot::from(slot));
+    CollatorOffences::on_initialize(number);
+}
+
+/// A header of height `number` built on `relay_parent` and sealed by `signer` in `slot`.
+pub fn sealed_header(
+    number: u64,
+    slot: u64,
+    signer: u64,
+    parent_hash: H256,
+    relay_parent: H256,
+) -> Header {
+    let mut header = Header::new(
+        number,
+        Default::default(),
+        Default::default(),
+        parent_hash,
+        Default::default(),
+    );
+    header
+        .digest_mut()
+        .push(relay_parent_storage_root_item(relay_parent, 10));
+    header.digest_mut().push(
+        <DigestItem as CompatibleDigestItem<TestSignature>>::aura_pre_digest(Slot::from(slot)),
+    );
+    let signature = UintAuthorityId(signer).sign(&header.hash()).unwrap();
+    header
+        .digest_mut()
+        .push(<DigestItem as CompatibleDigestItem<TestSignature>>::aura_seal(signature));
+    header
+}
+
+/// Two different headers of height 2 that `signer` sealed in `slot` on the same relay parent,
+/// attributed to `offender`.
+pub fn equivocation(offender: u64, signer: u64, slot: u64) -> EquivocationProof<Test> {
+    EquivocationProof::<Test> {
+        offender: UintAuthorityId(offender),
+        slot: Slot::from(slot),
+        first_header: sealed_header(2, slot, signer, H256::repeat_byte(1), RELAY_PARENT),
+        second_header: sealed_header(2, slot, signer, H256::repeat_byte(2), RELAY_PARENT),
+    }
+}
+
+/// The events deposited by the pallet, oldest first.
+pub fn offence_events() -> Vec<Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            RuntimeEvent::CollatorOffences(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
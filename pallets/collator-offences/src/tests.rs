@@ -0,0 +1,318 @@
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, pallet_prelude::*};
+use sp_consensus_aura::Slot;
+use sp_runtime::{testing::H256, traits::Header as _};
+
+fn report(proof: EquivocationProof<Test>) -> DispatchResult {
+    CollatorOffences::report_equivocation_unsigned(RuntimeOrigin::none(), Box::new(proof))
+}
+
+fn set_slot(slot: u64) {
+    pallet_aura::CurrentSlot::<Test>::put(Slot::from(slot));
+}
+
+fn disabled() -> Vec<u32> {
+    pallet_session::DisabledValidators::<Test>::get()
+}
+
+fn missed(collator: u64) -> u32 {
+    MissedSlots::<Test>::get(collator)
+}
+
+#[test]
+fn valid_equivocation_is_accepted() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        assert_eq!(
+            CollatorOffences::check_equivocation(&equivocation(3, 3, 20)).ok(),
+            Some(3)
+        );
+    });
+}
+
+#[test]
+fn report_equivocation_punishes_the_offender() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        assert_ok!(report(equivocation(3, 3, 20)));
+
+        assert_eq!(Slashes::get(), vec![(3, Perbill::from_percent(10))]);
+        // Collator 3 is at index 2 of the session's collators.
+        assert_eq!(disabled(), vec![2]);
+        assert_eq!(LastEquivocation::<Test>::get(3), Some(Slot::from(20)));
+        assert_eq!(
+            offence_events(),
+            vec![Event::CollatorPunished {
+                collator: 3,
+                offence: Offence::Equivocation,
+                disabled: true,
+            }]
+        );
+    });
+}
+
+#[test]
+fn identical_headers_are_no_equivocation() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        let mut proof = equivocation(3, 3, 20);
+        proof.second_header = proof.first_header.clone();
+
+        assert_noop!(report(proof), Error::<Test>::InvalidEquivocationProof);
+    });
+}
+
+#[test]
+fn headers_of_different_heights_are_no_equivocation() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        let mut proof = equivocation(3, 3, 20);
+        proof.second_header = sealed_header(3, 20, 3, H256::repeat_byte(2), RELAY_PARENT);
+
+        assert_noop!(report(proof), Error::<Test>::InvalidEquivocationProof);
+    });
+}
+
+#[test]
+fn headers_on_different_relay_parents_are_no_equivocation() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        // Collators build on every relay chain fork, which can share a slot.
+        let mut proof = equivocation(3, 3, 20);
+        proof.second_header = sealed_header(2, 20, 3, H256::repeat_byte(2), H256::repeat_byte(0xbb));
+
+        assert_noop!(report(proof), Error::<Test>::InvalidEquivocationProof);
+    });
+}
+
+#[test]
+fn headers_must_be_sealed_by_the_offender_in_the_slot() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        // Sealed by collator 4, but reported against collator 3.
+        assert_noop!(
+            report(equivocation(3, 4, 20)),
+            Error::<Test>::InvalidEquivocationProof
+        );
+
+        let mut proof = equivocation(3, 3, 20);
+        proof.slot = Slot::from(19);
+        assert_noop!(report(proof), Error::<Test>::InvalidEquivocationProof);
+
+        let mut proof = equivocation(3, 3, 20);
+        proof.second_header.digest_mut().pop();
+        assert_noop!(report(proof), Error::<Test>::InvalidEquivocationProof);
+    });
+}
+
+#[test]
+fn stale_equivocation_is_rejected() {
+    new_test_ext().execute_with(|| {
+        set_slot(20 + REPORT_LONGEVITY + 1);
+        assert_noop!(
+            report(equivocation(3, 3, 20)),
+            Error::<Test>::StaleEquivocationProof
+        );
+
+        set_slot(20 + REPORT_LONGEVITY);
+        assert_ok!(report(equivocation(3, 3, 20)));
+    });
+}
+
+#[test]
+fn offender_must_be_a_collator() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        assert_noop!(
+            report(equivocation(99, 99, 20)),
+            Error::<Test>::UnknownOffender
+        );
+    });
+}
+
+#[test]
+fn equivocation_is_only_punished_once() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        assert_ok!(report(equivocation(3, 3, 20)));
+
+        assert_noop!(
+            report(equivocation(3, 3, 20)),
+            Error::<Test>::DuplicateEquivocationReport
+        );
+        assert_noop!(
+            report(equivocation(3, 3, 19)),
+            Error::<Test>::DuplicateEquivocationReport
+        );
+
+        // A later equivocation is a new offence.
+        assert_ok!(report(equivocation(3, 3, 21)));
+        assert_eq!(Slashes::get().len(), 2);
+    });
+}
+
+#[test]
+fn reports_are_only_valid_from_the_local_node() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        let call = Call::report_equivocation_unsigned {
+            proof: Box::new(equivocation(3, 3, 20)),
+        };
+
+        assert_eq!(
+            CollatorOffences::validate_unsigned(TransactionSource::External, &call),
+            InvalidTransaction::Call.into()
+        );
+        assert_ok!(CollatorOffences::validate_unsigned(
+            TransactionSource::InBlock,
+            &call
+        ));
+
+        let valid = CollatorOffences::validate_unsigned(TransactionSource::Local, &call).unwrap();
+        assert_eq!(valid.priority, TransactionPriority::MAX);
+        assert_eq!(valid.longevity, REPORT_LONGEVITY);
+        assert!(!valid.propagate);
+    });
+}
+
+#[test]
+fn invalid_report_fails_validation() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        let call = Call::report_equivocation_unsigned {
+            proof: Box::new(equivocation(3, 4, 20)),
+        };
+
+        assert_eq!(
+            CollatorOffences::validate_unsigned(TransactionSource::Local, &call),
+            InvalidTransaction::BadProof.into()
+        );
+    });
+}
+
+#[test]
+fn skipped_slots_are_counted_against_their_collators() {
+    new_test_ext().execute_with(|| {
+        // Nothing is counted before the first slot is known.
+        block_in_slot(10);
+        assert_eq!(MissedSlots::<Test>::iter().count(), 0);
+
+        // Slots 11 and 12 belong to the collators at index 4 and 5.
+        block_in_slot(13);
+        assert_eq!(missed(5), 1);
+        assert_eq!(missed(6), 1);
+        assert_eq!(MissedSlots::<Test>::iter().count(), 2);
+        assert_eq!(LastSlot::<Test>::get(), Slot::from(13));
+
+        block_in_slot(14);
+        assert_eq!(MissedSlots::<Test>::iter().count(), 2);
+    });
+}
+
+#[test]
+fn stalled_chain_counts_no_missed_slots() {
+    new_test_ext().execute_with(|| {
+        block_in_slot(10);
+        // Every collator's slot was skipped.
+        block_in_slot(10 + COLLATORS.len() as u64 + 1);
+
+        assert_eq!(MissedSlots::<Test>::iter().count(), 0);
+    });
+}
+
+#[test]
+fn missing_max_missed_slots_punishes_once() {
+    new_test_ext().execute_with(|| {
+        // Slots 11, 18 and 25 belong to collator 5.
+        for slot in [10, 12, 17, 19, 24] {
+            block_in_slot(slot);
+        }
+        assert_eq!(missed(5), 2);
+        assert!(Slashes::get().is_empty());
+
+        block_in_slot(26);
+        assert_eq!(missed(5), 3);
+        assert_eq!(Slashes::get(), vec![(5, Perbill::from_percent(1))]);
+        assert_eq!(disabled(), vec![4]);
+        assert_eq!(
+            offence_events(),
+            vec![Event::CollatorPunished {
+                collator: 5,
+                offence: Offence::MissedSlots,
+                disabled: true,
+            }]
+        );
+
+        // Disabled collators have no slots to miss.
+        for slot in 27..=31 {
+            block_in_slot(slot);
+        }
+        block_in_slot(33);
+        assert_eq!(missed(5), 3);
+        assert_eq!(Slashes::get().len(), 1);
+    });
+}
+
+#[test]
+fn disabled_collators_miss_no_slots() {
+    new_test_ext().execute_with(|| {
+        assert!(Session::disable(&5));
+
+        block_in_slot(10);
+        block_in_slot(12);
+        assert_eq!(missed(5), 0);
+    });
+}
+
+#[test]
+fn missed_slots_are_reset_every_session() {
+    new_test_ext().execute_with(|| {
+        block_in_slot(10);
+        block_in_slot(12);
+        assert_eq!(missed(5), 1);
+
+        // The gap into the new session is not counted.
+        pallet_session::CurrentIndex::<Test>::put(1);
+        block_in_slot(14);
+        assert_eq!(MissedSlots::<Test>::iter().count(), 0);
+        assert_eq!(TrackedSession::<Test>::get(), 1);
+
+        block_in_slot(16);
+        assert_eq!(missed(2), 1);
+    });
+}
+
+#[test]
+fn at_most_a_third_of_the_collators_is_disabled() {
+    new_test_ext().execute_with(|| {
+        set_slot(20);
+        for offender in 1..=3 {
+            assert_ok!(report(equivocation(offender, offender, 20)));
+        }
+
+        // Two of the seven collators can be disabled, the third offender is only slashed.
+        assert_eq!(disabled(), vec![0, 1]);
+        assert_eq!(Slashes::get().len(), 3);
+        assert_eq!(
+            offence_events().last(),
+            Some(&Event::CollatorPunished {
+                collator: 3,
+                offence: Offence::Equivocation,
+                disabled: false,
+            })
+        );
+    });
+}
+
+#[test]
+fn zero_max_missed_slots_disables_tracking() {
+    new_test_ext().execute_with(|| {
+        MaxMissedSlots::set(0);
+        block_in_slot(10);
+        block_in_slot(13);
+
+        assert_eq!(MissedSlots::<Test>::iter().count(), 0);
+        assert!(Slashes::get().is_empty());
+        assert_eq!(LastSlot::<Test>::get(), Slot::from(13));
+    });
+}
@@ -0,0 +1,42 @@
+//! Weights for `pallet_collator_offences`.
+//!
+//! The `()` implementation estimates each call from its storage accesses and is meant for tests.
+//! Runtimes should use weights generated from the pallet's benchmarks.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+
+/// Weight functions needed for `pallet_collator_offences`.
+pub trait WeightInfo {
+    fn report_equivocation() -> Weight;
+    fn note_missed_slots(s: u32) -> Weight;
+    fn punish() -> Weight;
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+    fn report_equivocation() -> Weight {
+        // Two signature checks on top of punishing the offender.
+        Weight::from_parts(150_000_000, 4_000)
+            .saturating_add(RocksDbWeight::get().reads(4_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(Self::punish())
+    }
+    fn note_missed_slots(s: u32) -> Weight {
+        Weight::from_parts(10_000_000, 4_000)
+            .saturating_add(Weight::from_parts(5_000_000, 3_000).saturating_mul(s.into()))
+            .saturating_add(RocksDbWeight::get().reads(2_u64))
+            .saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(s.into())))
+            .saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(s.into())))
+    }
+    fn punish() -> Weight {
+        Weight::from_parts(80_000_000, 8_000)
+            .saturating_add(RocksDbWeight::get().reads(8_u64))
+            .saturating_add(RocksDbWeight::get().writes(6_u64))
+    }
+}
@@ -0,0 +1,327 @@
+//! Benchmarking setup for pallet-collator-staking
+
+#![cfg(feature = "runtime-benchmarks")]
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as CollatorStaking;
+use codec::{Decode, Encode};
+use frame_benchmarking::{account, v2::*, whitelisted_caller};
+use frame_support::traits::{
+    fungible::{Inspect, Mutate},
+    Get,
+};
+use frame_system::{pallet_prelude::BlockNumberFor, EventRecord, RawOrigin};
+use pallet_authorship::EventHandler;
+use pallet_session::{self as session, SessionManager};
+use sp_runtime::{
+    traits::{Saturating, TrailingZeroInput, Zero},
+    Perbill,
+};
+use sp_std::prelude::*;
+
+const SEED: u32 = 0;
+
+fn assert_last_event<T: Config>(generic_event: <T as Config>::RuntimeEvent) {
+    let events = frame_system::Pallet::<T>::events();
+    let system_event: <T as frame_system::Config>::RuntimeEvent = generic_event.into();
+    let EventRecord { event, .. } = &events[events.len() - 1];
+    assert_eq!(event, &system_event);
+}
+
+/// Enough to bond, delegate and top up several times over.
+fn stake<T: Config>() -> BalanceOf<T> {
+    T::MinCandidateBond::get()
+        .max(T::MinDelegation::get())
+        .max(T::Currency::minimum_balance())
+}
+
+fn fund<T: Config>(who: &T::AccountId) {
+    T::Currency::set_balance(who, stake::<T>().saturating_mul(100u32.into()));
+}
+
+/// Register session keys for `who`, derived from the account so they are unique.
+fn set_keys<T: Config + session::Config>(who: &T::AccountId) {
+    let keys = <T as session::Config>::Keys::decode(&mut TrailingZeroInput::new(&who.encode()))
+        .expect("keys can be decoded from any input");
+    <session::Pallet<T>>::set_keys(RawOrigin::Signed(who.clone()).into(), keys, Vec::new())
+        .expect("fresh keys can be set");
+}
+
+fn register_candidate<T: Config + session::Config>(who: &T::AccountId) {
+    fund::<T>(who);
+    set_keys::<T>(who);
+    <CollatorStaking<T>>::register_as_candidate(
+        RawOrigin::Signed(who.clone()).into(),
+        stake::<T>(),
+        Perbill::from_percent(10),
+    )
+    .expect("funded account with keys can register");
+}
+
+fn register_candidates<T: Config + session::Config>(count: u32) -> Vec<T::AccountId> {
+    (0..count)
+        .map(|c| {
+            let who = account("candidate", c, SEED);
+            register_candidate::<T>(&who);
+            who
+        })
+        .collect()
+}
+
+fn delegate_to<T: Config>(delegator: &T::AccountId, candidate: &T::AccountId) {
+    fund::<T>(delegator);
+    <CollatorStaking<T>>::delegate(
+        RawOrigin::Signed(delegator.clone()).into(),
+        candidate.clone(),
+        stake::<T>(),
+    )
+    .expect("funded account can delegate");
+}
+
+/// Put rewards in the pot and credit them to `candidate`'s delegators.
+fn reward<T: Config>(candidate: &T::AccountId) {
+    let pot = <CollatorStaking<T>>::account_id();
+    T::Currency::set_balance(&pot, stake::<T>().saturating_mul(1000u32.into()));
+    BlocksAuthored::<T>::insert(candidate, 1);
+    <CollatorStaking<T> as SessionManager<_>>::end_session(0);
+}
+
+#[benchmarks(where T: session::Config)]
+mod benchmarks {
+    use super::*;
+
+    #[benchmark]
+    fn register_as_candidate() {
+        let caller: T::AccountId = whitelisted_caller();
+        fund::<T>(&caller);
+        set_keys::<T>(&caller);
+        let bond = stake::<T>();
+
+        #[extrinsic_call]
+        _(
+            RawOrigin::Signed(caller.clone()),
+            bond,
+            Perbill::from_percent(10),
+        );
+
+        assert_last_event::<T>(
+            Event::CandidateRegistered {
+                candidate: caller,
+                bond,
+                commission: Perbill::from_percent(10),
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn candidate_bond_more() {
+        let caller: T::AccountId = whitelisted_caller();
+        register_candidate::<T>(&caller);
+        let amount = stake::<T>();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), amount);
+
+        assert_last_event::<T>(
+            Event::CandidateBondIncreased {
+                candidate: caller,
+                amount,
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn candidate_bond_less() {
+        let caller: T::AccountId = whitelisted_caller();
+        register_candidate::<T>(&caller);
+        let amount = stake::<T>();
+        <CollatorStaking<T>>::candidate_bond_more(RawOrigin::Signed(caller.clone()).into(), amount)
+            .unwrap();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), amount);
+
+        assert_last_event::<T>(
+            Event::CandidateBondDecreased {
+                candidate: caller,
+                amount,
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn set_commission() {
+        let caller: T::AccountId = whitelisted_caller();
+        register_candidate::<T>(&caller);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), Perbill::from_percent(20));
+
+        assert_last_event::<T>(
+            Event::CommissionSet {
+                candidate: caller,
+                commission: Perbill::from_percent(20),
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn leave_candidates(d: Linear<0, { T::MaxDelegatorsPerCandidate::get() }>) {
+        register_candidates::<T>(T::MinEligibleCollators::get());
+        let caller: T::AccountId = whitelisted_caller();
+        register_candidate::<T>(&caller);
+        for i in 0..d {
+            delegate_to::<T>(&account("delegator", i, SEED), &caller);
+        }
+        reward::<T>(&caller);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), d);
+
+        assert_last_event::<T>(Event::CandidateLeft { candidate: caller }.into());
+    }
+
+    #[benchmark]
+    fn delegate() {
+        let candidate = register_candidates::<T>(1).remove(0);
+        let caller: T::AccountId = whitelisted_caller();
+        // Topping up an existing delegation also pays out its rewards.
+        delegate_to::<T>(&caller, &candidate);
+        reward::<T>(&candidate);
+        let amount = stake::<T>();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), candidate.clone(), amount);
+
+        assert_last_event::<T>(
+            Event::Delegated {
+                delegator: caller,
+                candidate,
+                amount,
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn undelegate() {
+        let candidate = register_candidates::<T>(1).remove(0);
+        let caller: T::AccountId = whitelisted_caller();
+        delegate_to::<T>(&caller, &candidate);
+        reward::<T>(&candidate);
+        let amount = stake::<T>();
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), candidate.clone(), amount);
+
+        assert_last_event::<T>(
+            Event::Undelegated {
+                delegator: caller,
+                candidate,
+                amount,
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn claim_rewards() {
+        let candidate = register_candidates::<T>(1).remove(0);
+        let caller: T::AccountId = whitelisted_caller();
+        delegate_to::<T>(&caller, &candidate);
+        reward::<T>(&candidate);
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()), candidate.clone());
+
+        assert!(
+            Delegations::<T>::get(&candidate, &caller).is_some_and(|delegation| {
+                let info = Candidates::<T>::get(&candidate).unwrap();
+                <CollatorStaking<T>>::pending_rewards(&info, &delegation).is_zero()
+            })
+        );
+    }
+
+    #[benchmark]
+    fn withdraw_unbonded() {
+        let candidate = register_candidates::<T>(1).remove(0);
+        let caller: T::AccountId = whitelisted_caller();
+        delegate_to::<T>(&caller, &candidate);
+        let amount = stake::<T>();
+        <CollatorStaking<T>>::undelegate(
+            RawOrigin::Signed(caller.clone()).into(),
+            candidate,
+            amount,
+        )
+        .unwrap();
+        CurrentSession::<T>::put(T::UnbondingSessions::get());
+
+        #[extrinsic_call]
+        _(RawOrigin::Signed(caller.clone()));
+
+        assert_last_event::<T>(
+            Event::Withdrawn {
+                who: caller,
+                amount,
+            }
+            .into(),
+        );
+    }
+
+    #[benchmark]
+    fn note_author() {
+        let author: T::AccountId = account("author", 0, SEED);
+        BlocksAuthored::<T>::insert(&author, 1);
+
+        #[block]
+        {
+            <CollatorStaking<T> as EventHandler<_, _>>::note_author(author.clone());
+        }
+
+        assert_eq!(BlocksAuthored::<T>::get(&author), 2);
+    }
+
+    #[benchmark]
+    fn new_session(c: Linear<1, { T::MaxCandidates::get() }>) {
+        register_candidates::<T>(c);
+        frame_system::Pallet::<T>::set_block_number(BlockNumberFor::<T>::from(1u32));
+
+        #[block]
+        {
+            <CollatorStaking<T> as SessionManager<_>>::new_session(1);
+        }
+
+        let expected = c.min(T::DesiredCollators::get()) as usize;
+        assert_eq!(<CollatorStaking<T>>::select_collators().len(), expected);
+    }
+
+    #[benchmark]
+    fn end_session(r: Linear<1, { T::MaxCandidates::get() }>) {
+        let candidates = register_candidates::<T>(r);
+        for (i, candidate) in candidates.iter().enumerate() {
+            delegate_to::<T>(&account("delegator", i as u32, SEED), candidate);
+            BlocksAuthored::<T>::insert(candidate, 1);
+        }
+        let pot = <CollatorStaking<T>>::account_id();
+        T::Currency::set_balance(&pot, stake::<T>().saturating_mul(1000u32.into()));
+
+        #[block]
+        {
+            <CollatorStaking<T> as SessionManager<_>>::end_session(0);
+        }
+
+        assert_eq!(BlocksAuthored::<T>::iter().count(), 0);
+    }
+
+    impl_benchmark_test_suite!(
+        CollatorStaking,
+        crate::mock::new_bench_ext(),
+        crate::mock::Test,
+    );
+}
@@ -0,0 +1,1019 @@
+//! # Collator Staking
+//!
+//! Selects the parachain's collators by stake.
+//!
+//! Candidates bond funds of their own and token holders delegate funds to the candidates they
+//! want to back. When a session is planned the [`Config::DesiredCollators`] candidates with the
+//! most stake behind them, own bond plus delegations, become the collators of that session.
+//!
+//! Rewards are collected in the pot account (see [`Pallet::account_id`]) and paid out when a
+//! session ends, in proportion to the blocks each collator authored during it. A collator keeps
+//! their commission plus the part of the remainder matching their own bond. The rest is shared
+//! between their delegators pro rata and can be claimed with [`Pallet::claim_rewards`].
+//!
+//! Bonds and delegations are kept on hold under [`HoldReason::Staking`]. Funds that are unbonded
+//! stay on hold for [`Config::UnbondingSessions`] sessions before they can be withdrawn with
+//! [`Pallet::withdraw_unbonded`].
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+pub use pallet::*;
+
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+pub use weights::WeightInfo;
+
+#[frame_support::pallet]
+pub mod pallet {
+    use super::WeightInfo;
+    use codec::{Decode, Encode, MaxEncodedLen};
+    use frame_support::{
+        dispatch::DispatchClass,
+        pallet_prelude::*,
+        traits::{
+            fungible::{Inspect, Mutate, MutateHold},
+            tokens::{Fortitude, Precision, Preservation, Restriction},
+            ValidatorRegistration,
+        },
+        PalletId,
+    };
+    use frame_system::pallet_prelude::*;
+    use pallet_session::SessionManager;
+    use scale_info::TypeInfo;
+    use sp_runtime::{
+        traits::{AccountIdConversion, Saturating, Zero},
+        FixedPointNumber, FixedU128, PerThing, Perbill, RuntimeDebug,
+    };
+    use sp_staking::SessionIndex;
+    use sp_std::prelude::*;
+
+    pub(crate) const LOG_TARGET: &str = "runtime::collator-staking";
+
+    pub type BalanceOf<T> =
+        <<T as Config>::Currency as Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+    /// A collator candidate.
+    #[derive(
+        Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default,
+    )]
+    pub struct CandidateInfo<Balance> {
+        /// The candidate's own bond.
+        pub bond: Balance,
+        /// The sum of all delegations to the candidate.
+        pub delegated: Balance,
+        /// Number of accounts delegating to the candidate.
+        pub delegators: u32,
+        /// Share of the candidate's rewards kept before anything is shared with delegators.
+        pub commission: Perbill,
+        /// Rewards paid to the candidate's delegators so far, per unit delegated.
+        pub reward_per_stake: FixedU128,
+    }
+
+    impl<Balance: Saturating + Copy> CandidateInfo<Balance> {
+        /// Own bond plus delegations; what candidates are ranked by.
+        pub fn total_stake(&self) -> Balance {
+            self.bond.saturating_add(self.delegated)
+        }
+    }
+
+    /// Funds an account delegates to a candidate.
+    #[derive(
+        Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo, Default,
+    )]
+    pub struct Delegation<Balance> {
+        /// The delegated amount.
+        pub amount: Balance,
+        /// Rewards that accrued to `amount` before the delegator was entitled to them, or that
+        /// have already been paid out.
+        pub reward_debt: Balance,
+    }
+
+    /// Funds on their way out of staking.
+    #[derive(Clone, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+    pub struct UnbondingChunk<Balance> {
+        /// The amount that is unbonding.
+        pub amount: Balance,
+        /// The session from which the amount can be withdrawn.
+        pub unlock_at: SessionIndex,
+    }
+
+    #[pallet::pallet]
+    pub struct Pallet<T>(_);
+
+    /// A reason for the pallet placing a hold on funds.
+    #[pallet::composite_enum]
+    pub enum HoldReason {
+        /// Funds bonded by a candidate or delegated to one, including funds that are unbonding.
+        #[codec(index = 0)]
+        Staking,
+    }
+
+    #[pallet::config]
+    pub trait Config: frame_system::Config {
+        /// Overarching event type.
+        type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+        /// The currency used for bonds, delegations and rewards.
+        type Currency: Mutate<Self::AccountId>
+            + MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>;
+
+        /// The overarching hold reason.
+        type RuntimeHoldReason: From<HoldReason>;
+
+        /// Account identifier of the pot that collators are rewarded from.
+        #[pallet::constant]
+        type PotId: Get<PalletId>;
+
+        /// Maximum number of candidates.
+        #[pallet::constant]
+        type MaxCandidates: Get<u32>;
+
+        /// Minimum number of candidates. The last candidates cannot leave.
+        #[pallet::constant]
+        type MinEligibleCollators: Get<u32>;
+
+        /// Number of collators selected for each session.
+        type DesiredCollators: Get<u32>;
+
+        /// Minimum bond of a candidate.
+        type MinCandidateBond: Get<BalanceOf<Self>>;
+
+        /// Minimum amount an account can delegate to a candidate.
+        type MinDelegation: Get<BalanceOf<Self>>;
+
+        /// Maximum number of delegators a single candidate can have.
+        #[pallet::constant]
+        type MaxDelegatorsPerCandidate: Get<u32>;
+
+        /// Maximum number of candidates a single account can delegate to.
+        #[pallet::constant]
+        type MaxDelegationsPerDelegator: Get<u32>;
+
+        /// Number of sessions unbonded funds stay on hold before they can be withdrawn.
+        #[pallet::constant]
+        type UnbondingSessions: Get<SessionIndex>;
+
+        /// Maximum number of unbonding chunks an account can have at once.
+        #[pallet::constant]
+        type MaxUnbondingChunks: Get<u32>;
+
+        /// Checks that a candidate has registered session keys.
+        type ValidatorRegistration: ValidatorRegistration<Self::AccountId>;
+
+        /// Account that receives slashed funds.
+        type SlashBeneficiary: Get<Self::AccountId>;
+
+        /// The weight information of this pallet.
+        type WeightInfo: WeightInfo;
+    }
+
+    /// The collator candidates.
+    #[pallet::storage]
+    pub type Candidates<T: Config> =
+        CountedStorageMap<_, Twox64Concat, T::AccountId, CandidateInfo<BalanceOf<T>>, OptionQuery>;
+
+    /// Delegations, keyed by candidate and then delegator.
+    #[pallet::storage]
+    pub type Delegations<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        T::AccountId,
+        Twox64Concat,
+        T::AccountId,
+        Delegation<BalanceOf<T>>,
+        OptionQuery,
+    >;
+
+    /// The candidates each account delegates to.
+    #[pallet::storage]
+    pub type DelegatorTargets<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        T::AccountId,
+        BoundedVec<T::AccountId, T::MaxDelegationsPerDelegator>,
+        ValueQuery,
+    >;
+
+    /// Funds each account is unbonding, oldest first.
+    #[pallet::storage]
+    pub type Unbonding<T: Config> = StorageMap<
+        _,
+        Twox64Concat,
+        T::AccountId,
+        BoundedVec<UnbondingChunk<BalanceOf<T>>, T::MaxUnbondingChunks>,
+        ValueQuery,
+    >;
+
+    /// Blocks authored by each collator in the current session.
+    #[pallet::storage]
+    pub type BlocksAuthored<T: Config> = StorageMap<_, Twox64Concat, T::AccountId, u32, ValueQuery>;
+
+    /// The index of the current session.
+    #[pallet::storage]
+    pub type CurrentSession<T: Config> = StorageValue<_, SessionIndex, ValueQuery>;
+
+    /// Rewards in the pot that belong to delegators and have not been claimed yet.
+    #[pallet::storage]
+    pub type UnclaimedRewards<T: Config> = StorageValue<_, BalanceOf<T>, ValueQuery>;
+
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        /// The initial candidates and their bonds.
+        pub candidates: Vec<(T::AccountId, BalanceOf<T>)>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            assert!(
+                self.candidates.len() as u32 <= T::MaxCandidates::get(),
+                "genesis candidates are more than T::MaxCandidates",
+            );
+            for (who, bond) in &self.candidates {
+                assert!(
+                    !Candidates::<T>::contains_key(who),
+                    "duplicate genesis candidate"
+                );
+                assert!(
+                    *bond >= T::MinCandidateBond::get(),
+                    "genesis candidate bond is below T::MinCandidateBond"
+                );
+                T::Currency::hold(&HoldReason::Staking.into(), who, *bond)
+                    .expect("genesis candidates must be able to pay their bond");
+                Candidates::<T>::insert(
+                    who,
+                    CandidateInfo {
+                        bond: *bond,
+                        ..Default::default()
+                    },
+                );
+            }
+
+            // Rewards are paid while keeping the pot alive, so it needs to exist from the start.
+            let pot = Pallet::<T>::account_id();
+            if T::Currency::balance(&pot).is_zero() {
+                let _ = T::Currency::mint_into(&pot, T::Currency::minimum_balance());
+            }
+        }
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// A new candidate joined.
+        CandidateRegistered {
+            candidate: T::AccountId,
+            bond: BalanceOf<T>,
+            commission: Perbill,
+        },
+        /// A candidate increased their bond.
+        CandidateBondIncreased {
+            candidate: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// A candidate started unbonding part of their bond.
+        CandidateBondDecreased {
+            candidate: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// A candidate changed their commission.
+        CommissionSet {
+            candidate: T::AccountId,
+            commission: Perbill,
+        },
+        /// A candidate left. Their bond and all delegations to them are unbonding.
+        CandidateLeft { candidate: T::AccountId },
+        /// An account delegated to a candidate.
+        Delegated {
+            delegator: T::AccountId,
+            candidate: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// An account started unbonding a delegation.
+        Undelegated {
+            delegator: T::AccountId,
+            candidate: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// Funds were moved into the unbonding queue of an account.
+        Unbonded {
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+            unlock_at: SessionIndex,
+        },
+        /// Unbonded funds were released.
+        Withdrawn {
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// Part of a collator's bond was slashed.
+        Slashed {
+            who: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// A delegator was paid their share of a candidate's rewards.
+        RewardsClaimed {
+            delegator: T::AccountId,
+            candidate: T::AccountId,
+            amount: BalanceOf<T>,
+        },
+        /// A collator was rewarded for the blocks they authored in the session that ended.
+        CollatorRewarded {
+            collator: T::AccountId,
+            collator_reward: BalanceOf<T>,
+            delegator_reward: BalanceOf<T>,
+        },
+        /// The collators of a session were selected.
+        CollatorsSelected {
+            session: SessionIndex,
+            collators: Vec<T::AccountId>,
+        },
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The account is already a candidate.
+        AlreadyCandidate,
+        /// The account is not a candidate.
+        NotCandidate,
+        /// There are already `MaxCandidates` candidates.
+        TooManyCandidates,
+        /// Leaving would take the number of candidates below `MinEligibleCollators`.
+        TooFewEligibleCollators,
+        /// The bond is below `MinCandidateBond`.
+        BondTooLow,
+        /// The account has not registered session keys.
+        ValidatorNotRegistered,
+        /// Candidates cannot delegate to themselves.
+        CannotDelegateSelf,
+        /// The delegation is below `MinDelegation`.
+        DelegationTooLow,
+        /// The account does not delegate to this candidate.
+        NotDelegating,
+        /// The candidate already has `MaxDelegatorsPerCandidate` delegators.
+        TooManyDelegators,
+        /// The account already delegates to `MaxDelegationsPerDelegator` candidates.
+        TooManyDelegations,
+        /// The amount is more than what is bonded or delegated.
+        InsufficientStake,
+        /// The account already has `MaxUnbondingChunks` unbonding chunks.
+        TooManyUnbondingChunks,
+        /// No unbonding funds can be withdrawn yet.
+        NothingToWithdraw,
+        /// The given number of delegators is lower than the candidate's.
+        InvalidDelegatorCount,
+    }
+
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn integrity_test() {
+            assert!(
+                T::MaxUnbondingChunks::get() > 0,
+                "there must be room for at least one unbonding chunk"
+            );
+            assert!(
+                T::MinEligibleCollators::get() <= T::MaxCandidates::get(),
+                "MinEligibleCollators can never be reached with MaxCandidates"
+            );
+        }
+    }
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Become a collator candidate, bonding `bond` and keeping `commission` of the rewards.
+        ///
+        /// The caller must have registered session keys.
+        #[pallet::call_index(0)]
+        #[pallet::weight(T::WeightInfo::register_as_candidate())]
+        pub fn register_as_candidate(
+            origin: OriginFor<T>,
+            #[pallet::compact] bond: BalanceOf<T>,
+            commission: Perbill,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            ensure!(
+                !Candidates::<T>::contains_key(&who),
+                Error::<T>::AlreadyCandidate
+            );
+            ensure!(
+                Candidates::<T>::count() < T::MaxCandidates::get(),
+                Error::<T>::TooManyCandidates
+            );
+            ensure!(bond >= T::MinCandidateBond::get(), Error::<T>::BondTooLow);
+            ensure!(
+                T::ValidatorRegistration::is_registered(&who),
+                Error::<T>::ValidatorNotRegistered
+            );
+
+            T::Currency::hold(&HoldReason::Staking.into(), &who, bond)?;
+            Candidates::<T>::insert(
+                &who,
+                CandidateInfo {
+                    bond,
+                    commission,
+                    ..Default::default()
+                },
+            );
+
+            Self::deposit_event(Event::CandidateRegistered {
+                candidate: who,
+                bond,
+                commission,
+            });
+            Ok(())
+        }
+
+        /// Add `amount` to the caller's candidate bond.
+        #[pallet::call_index(1)]
+        #[pallet::weight(T::WeightInfo::candidate_bond_more())]
+        pub fn candidate_bond_more(
+            origin: OriginFor<T>,
+            #[pallet::compact] amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut info = Candidates::<T>::get(&who).ok_or(Error::<T>::NotCandidate)?;
+            T::Currency::hold(&HoldReason::Staking.into(), &who, amount)?;
+            info.bond.saturating_accrue(amount);
+            Candidates::<T>::insert(&who, info);
+
+            Self::deposit_event(Event::CandidateBondIncreased {
+                candidate: who,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Start unbonding `amount` of the caller's candidate bond.
+        ///
+        /// The remaining bond must stay at or above `MinCandidateBond`.
+        #[pallet::call_index(2)]
+        #[pallet::weight(T::WeightInfo::candidate_bond_less())]
+        pub fn candidate_bond_less(
+            origin: OriginFor<T>,
+            #[pallet::compact] amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut info = Candidates::<T>::get(&who).ok_or(Error::<T>::NotCandidate)?;
+            ensure!(amount <= info.bond, Error::<T>::InsufficientStake);
+            info.bond.saturating_reduce(amount);
+            ensure!(
+                info.bond >= T::MinCandidateBond::get(),
+                Error::<T>::BondTooLow
+            );
+
+            Self::unbond(&who, amount, false)?;
+            Candidates::<T>::insert(&who, info);
+
+            Self::deposit_event(Event::CandidateBondDecreased {
+                candidate: who,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Change the share of rewards the caller keeps as a candidate.
+        ///
+        /// Applies to rewards paid from the end of the current session on.
+        #[pallet::call_index(3)]
+        #[pallet::weight(T::WeightInfo::set_commission())]
+        pub fn set_commission(origin: OriginFor<T>, commission: Perbill) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            Candidates::<T>::try_mutate(&who, |maybe_info| -> DispatchResult {
+                let info = maybe_info.as_mut().ok_or(Error::<T>::NotCandidate)?;
+                info.commission = commission;
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::CommissionSet {
+                candidate: who,
+                commission,
+            });
+            Ok(())
+        }
+
+        /// Stop being a candidate.
+        ///
+        /// Pending rewards are paid to the delegators, and the caller's bond and all delegations
+        /// start unbonding. `delegators` must be at least the caller's number of delegators.
+        ///
+        /// A collator of the current or next session keeps collating until their sessions end.
+        #[pallet::call_index(4)]
+        #[pallet::weight(T::WeightInfo::leave_candidates(*delegators))]
+        pub fn leave_candidates(origin: OriginFor<T>, delegators: u32) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let info = Candidates::<T>::get(&who).ok_or(Error::<T>::NotCandidate)?;
+            ensure!(
+                delegators >= info.delegators,
+                Error::<T>::InvalidDelegatorCount
+            );
+            ensure!(
+                Candidates::<T>::count() > T::MinEligibleCollators::get(),
+                Error::<T>::TooFewEligibleCollators
+            );
+
+            Self::remove_candidate(&who, info);
+            Ok(())
+        }
+
+        /// Delegate `amount` to `candidate`, or add it to an existing delegation.
+        ///
+        /// Adding to an existing delegation pays out its pending rewards first.
+        #[pallet::call_index(5)]
+        #[pallet::weight(T::WeightInfo::delegate())]
+        pub fn delegate(
+            origin: OriginFor<T>,
+            candidate: T::AccountId,
+            #[pallet::compact] amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+            ensure!(who != candidate, Error::<T>::CannotDelegateSelf);
+
+            let mut info = Candidates::<T>::get(&candidate).ok_or(Error::<T>::NotCandidate)?;
+            let mut delegation = match Delegations::<T>::get(&candidate, &who) {
+                Some(delegation) => {
+                    Self::pay_pending(&candidate, &info, &who, &delegation)?;
+                    delegation
+                }
+                None => {
+                    ensure!(
+                        amount >= T::MinDelegation::get(),
+                        Error::<T>::DelegationTooLow
+                    );
+                    ensure!(
+                        info.delegators < T::MaxDelegatorsPerCandidate::get(),
+                        Error::<T>::TooManyDelegators
+                    );
+                    DelegatorTargets::<T>::try_mutate(&who, |targets| {
+                        targets.try_push(candidate.clone())
+                    })
+                    .map_err(|_| Error::<T>::TooManyDelegations)?;
+                    info.delegators.saturating_inc();
+                    Delegation::default()
+                }
+            };
+
+            T::Currency::hold(&HoldReason::Staking.into(), &who, amount)?;
+            delegation.amount.saturating_accrue(amount);
+            delegation.reward_debt = info.reward_per_stake.saturating_mul_int(delegation.amount);
+            info.delegated.saturating_accrue(amount);
+            Delegations::<T>::insert(&candidate, &who, delegation);
+            Candidates::<T>::insert(&candidate, info);
+
+            Self::deposit_event(Event::Delegated {
+                delegator: who,
+                candidate,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Start unbonding `amount` of the caller's delegation to `candidate`.
+        ///
+        /// Pending rewards are paid out first. What remains delegated must be zero or at least
+        /// `MinDelegation`.
+        #[pallet::call_index(6)]
+        #[pallet::weight(T::WeightInfo::undelegate())]
+        pub fn undelegate(
+            origin: OriginFor<T>,
+            candidate: T::AccountId,
+            #[pallet::compact] amount: BalanceOf<T>,
+        ) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut delegation =
+                Delegations::<T>::get(&candidate, &who).ok_or(Error::<T>::NotDelegating)?;
+            let mut info = Candidates::<T>::get(&candidate).ok_or(Error::<T>::NotCandidate)?;
+            ensure!(amount <= delegation.amount, Error::<T>::InsufficientStake);
+            let remaining = delegation.amount.saturating_sub(amount);
+            ensure!(
+                remaining.is_zero() || remaining >= T::MinDelegation::get(),
+                Error::<T>::DelegationTooLow
+            );
+
+            Self::pay_pending(&candidate, &info, &who, &delegation)?;
+            Self::unbond(&who, amount, false)?;
+
+            if remaining.is_zero() {
+                Delegations::<T>::remove(&candidate, &who);
+                DelegatorTargets::<T>::mutate_exists(&who, |maybe_targets| {
+                    if let Some(targets) = maybe_targets {
+                        targets.retain(|c| c != &candidate);
+                        if targets.is_empty() {
+                            *maybe_targets = None;
+                        }
+                    }
+                });
+                info.delegators.saturating_dec();
+            } else {
+                delegation.amount = remaining;
+                delegation.reward_debt = info.reward_per_stake.saturating_mul_int(remaining);
+                Delegations::<T>::insert(&candidate, &who, delegation);
+            }
+            info.delegated.saturating_reduce(amount);
+            Candidates::<T>::insert(&candidate, info);
+
+            Self::deposit_event(Event::Undelegated {
+                delegator: who,
+                candidate,
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Pay out the rewards the caller's delegation to `candidate` has earned so far.
+        #[pallet::call_index(7)]
+        #[pallet::weight(T::WeightInfo::claim_rewards())]
+        pub fn claim_rewards(origin: OriginFor<T>, candidate: T::AccountId) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let mut delegation =
+                Delegations::<T>::get(&candidate, &who).ok_or(Error::<T>::NotDelegating)?;
+            let info = Candidates::<T>::get(&candidate).ok_or(Error::<T>::NotCandidate)?;
+
+            Self::pay_pending(&candidate, &info, &who, &delegation)?;
+            delegation.reward_debt = info.reward_per_stake.saturating_mul_int(delegation.amount);
+            Delegations::<T>::insert(&candidate, &who, delegation);
+            Ok(())
+        }
+
+        /// Release all of the caller's unbonding funds whose unbonding period is over.
+        #[pallet::call_index(8)]
+        #[pallet::weight(T::WeightInfo::withdraw_unbonded())]
+        pub fn withdraw_unbonded(origin: OriginFor<T>) -> DispatchResult {
+            let who = ensure_signed(origin)?;
+
+            let current = CurrentSession::<T>::get();
+            let mut chunks = Unbonding::<T>::get(&who);
+            let mut unlocked = BalanceOf::<T>::zero();
+            chunks.retain(|chunk| {
+                if chunk.unlock_at <= current {
+                    unlocked.saturating_accrue(chunk.amount);
+                    false
+                } else {
+                    true
+                }
+            });
+            ensure!(!unlocked.is_zero(), Error::<T>::NothingToWithdraw);
+
+            if chunks.is_empty() {
+                Unbonding::<T>::remove(&who);
+            } else {
+                Unbonding::<T>::insert(&who, chunks);
+            }
+
+            // Slashed funds are no longer on hold, release whatever is left of the chunks.
+            let amount = T::Currency::release(
+                &HoldReason::Staking.into(),
+                &who,
+                unlocked,
+                Precision::BestEffort,
+            )?;
+
+            Self::deposit_event(Event::Withdrawn { who, amount });
+            Ok(())
+        }
+    }
+
+    impl<T: Config> Pallet<T> {
+        /// Get a unique, inaccessible account ID from the `PotId`.
+        pub fn account_id() -> T::AccountId {
+            T::PotId::get().into_account_truncating()
+        }
+
+        /// Rewards `delegation` has earned from `candidate` and not been paid yet.
+        pub fn pending_rewards(
+            info: &CandidateInfo<BalanceOf<T>>,
+            delegation: &Delegation<BalanceOf<T>>,
+        ) -> BalanceOf<T> {
+            info.reward_per_stake
+                .saturating_mul_int(delegation.amount)
+                .saturating_sub(delegation.reward_debt)
+        }
+
+        /// Pay the pending rewards of `delegator`'s delegation to `candidate` out of the pot.
+        ///
+        /// The caller is responsible for updating the delegation's `reward_debt`.
+        fn pay_pending(
+            candidate: &T::AccountId,
+            info: &CandidateInfo<BalanceOf<T>>,
+            delegator: &T::AccountId,
+            delegation: &Delegation<BalanceOf<T>>,
+        ) -> DispatchResult {
+            let amount = Self::pending_rewards(info, delegation);
+            if amount.is_zero() {
+                return Ok(());
+            }
+
+            T::Currency::transfer(
+                &Self::account_id(),
+                delegator,
+                amount,
+                Preservation::Preserve,
+            )?;
+            UnclaimedRewards::<T>::mutate(|unclaimed| unclaimed.saturating_reduce(amount));
+
+            Self::deposit_event(Event::RewardsClaimed {
+                delegator: delegator.clone(),
+                candidate: candidate.clone(),
+                amount,
+            });
+            Ok(())
+        }
+
+        /// Move `amount` of the funds `who` has on hold into their unbonding queue.
+        ///
+        /// When the queue is full the amount is merged into the newest chunk if `force` is set,
+        /// pushing back its unlock, and refused otherwise.
+        fn unbond(who: &T::AccountId, amount: BalanceOf<T>, force: bool) -> DispatchResult {
+            if amount.is_zero() {
+                return Ok(());
+            }
+
+            let unlock_at = CurrentSession::<T>::get().saturating_add(T::UnbondingSessions::get());
+            Unbonding::<T>::try_mutate(who, |chunks| -> DispatchResult {
+                if let Some(last) = chunks.last_mut() {
+                    if last.unlock_at == unlock_at {
+                        last.amount.saturating_accrue(amount);
+                        return Ok(());
+                    }
+                }
+                if let Err(chunk) = chunks.try_push(UnbondingChunk { amount, unlock_at }) {
+                    ensure!(force, Error::<T>::TooManyUnbondingChunks);
+                    // `MaxUnbondingChunks` is at least one, so a full queue has a newest chunk.
+                    if let Some(last) = chunks.last_mut() {
+                        last.amount.saturating_accrue(chunk.amount);
+                        last.unlock_at = unlock_at;
+                    }
+                }
+                Ok(())
+            })?;
+
+            Self::deposit_event(Event::Unbonded {
+                who: who.clone(),
+                amount,
+                unlock_at,
+            });
+            Ok(())
+        }
+
+        /// Remove `candidate`, paying out and unbonding all of their delegations and their bond.
+        ///
+        /// Rewards that can't be paid out are forfeited to the pot, as the delegations they were
+        /// owed on are gone.
+        pub(crate) fn remove_candidate(
+            candidate: &T::AccountId,
+            info: CandidateInfo<BalanceOf<T>>,
+        ) {
+            for (delegator, delegation) in Delegations::<T>::drain_prefix(candidate) {
+                if let Err(e) = Self::pay_pending(candidate, &info, &delegator, &delegation) {
+                    let unpaid = Self::pending_rewards(&info, &delegation);
+                    UnclaimedRewards::<T>::mutate(|unclaimed| unclaimed.saturating_reduce(unpaid));
+                    log::warn!(
+                        target: LOG_TARGET,
+                        "failed to pay {:?} rewards to {:?}: {:?}",
+                        unpaid,
+                        delegator,
+                        e,
+                    );
+                }
+                DelegatorTargets::<T>::mutate_exists(&delegator, |maybe_targets| {
+                    if let Some(targets) = maybe_targets {
+                        targets.retain(|c| c != candidate);
+                        if targets.is_empty() {
+                            *maybe_targets = None;
+                        }
+                    }
+                });
+                let _ = Self::unbond(&delegator, delegation.amount, true);
+            }
+            let _ = Self::unbond(candidate, info.bond, true);
+            Candidates::<T>::remove(candidate);
+
+            Self::deposit_event(Event::CandidateLeft {
+                candidate: candidate.clone(),
+            });
+        }
+
+        /// Slash `fraction` of `who`'s bond and unbonding funds into `SlashBeneficiary`.
+        ///
+        /// Only the collator's own funds are slashed, never their delegators'. The slash is taken
+        /// from the bond first and then from the unbonding funds, newest first, so that unbonding
+        /// after an offence doesn't escape it. A candidate whose bond drops below
+        /// `MinCandidateBond` stays registered with their delegations, but isn't selected until
+        /// they bond more.
+        ///
+        /// Returns the amount slashed.
+        pub fn slash(who: &T::AccountId, fraction: Perbill) -> BalanceOf<T> {
+            let maybe_info = Candidates::<T>::get(who);
+            let mut chunks = Unbonding::<T>::get(who);
+            let bond_slash = maybe_info
+                .as_ref()
+                .map_or(Zero::zero(), |info| fraction * info.bond);
+            let unbonding = chunks
+                .iter()
+                .fold(Zero::zero(), |total: BalanceOf<T>, chunk| {
+                    total.saturating_add(chunk.amount)
+                });
+            let amount = bond_slash.saturating_add(fraction * unbonding);
+            if amount.is_zero() {
+                return amount;
+            }
+
+            let slashed = T::Currency::transfer_on_hold(
+                &HoldReason::Staking.into(),
+                who,
+                &T::SlashBeneficiary::get(),
+                amount,
+                Precision::BestEffort,
+                Restriction::Free,
+                Fortitude::Force,
+            )
+            .unwrap_or_else(|e| {
+                log::warn!(
+                    target: LOG_TARGET,
+                    "failed to slash {:?} from {:?}: {:?}",
+                    amount,
+                    who,
+                    e,
+                );
+                Zero::zero()
+            });
+
+            let from_bond = slashed.min(bond_slash);
+            let mut remaining = slashed.saturating_sub(from_bond);
+            if !remaining.is_zero() {
+                for chunk in chunks.iter_mut().rev() {
+                    let taken = remaining.min(chunk.amount);
+                    chunk.amount.saturating_reduce(taken);
+                    remaining.saturating_reduce(taken);
+                }
+                chunks.retain(|chunk| !chunk.amount.is_zero());
+                if chunks.is_empty() {
+                    Unbonding::<T>::remove(who);
+                } else {
+                    Unbonding::<T>::insert(who, chunks);
+                }
+            }
+            if let Some(mut info) = maybe_info {
+                info.bond.saturating_reduce(from_bond);
+                Candidates::<T>::insert(who, info);
+            }
+
+            Self::deposit_event(Event::Slashed {
+                who: who.clone(),
+                amount: slashed,
+            });
+            slashed
+        }
+
+        /// The registered candidates with the most stake, at most `DesiredCollators` of them.
+        ///
+        /// Candidates bonding less than `MinCandidateBond`, after a slash, are skipped.
+        pub fn select_collators() -> Vec<T::AccountId> {
+            let min_bond = T::MinCandidateBond::get();
+            let mut candidates = Candidates::<T>::iter()
+                .filter(|(who, info)| {
+                    info.bond >= min_bond && T::ValidatorRegistration::is_registered(who)
+                })
+                .map(|(who, info)| (who, info.total_stake()))
+                .collect::<Vec<_>>();
+            candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+            candidates.truncate(T::DesiredCollators::get() as usize);
+            candidates.into_iter().map(|(who, _)| who).collect()
+        }
+
+        /// Share the rewards in the pot between the collators of the session that ended, by
+        /// blocks authored.
+        ///
+        /// Returns the number of collators that were rewarded.
+        fn distribute_rewards() -> u32 {
+            let authored = BlocksAuthored::<T>::drain().collect::<Vec<_>>();
+            let total_blocks = authored
+                .iter()
+                .fold(0u32, |total, (_, blocks)| total.saturating_add(*blocks));
+            let pot = Self::account_id();
+            let available =
+                T::Currency::reducible_balance(&pot, Preservation::Preserve, Fortitude::Polite)
+                    .saturating_sub(UnclaimedRewards::<T>::get());
+            if total_blocks.is_zero() || available.is_zero() {
+                return authored.len() as u32;
+            }
+
+            for (collator, blocks) in &authored {
+                let reward = Perbill::from_rational(*blocks, total_blocks) * available;
+                Self::reward_collator(&pot, collator, reward);
+            }
+            authored.len() as u32
+        }
+
+        /// Pay `collator` their part of `reward` and credit the rest to their delegators.
+        fn reward_collator(pot: &T::AccountId, collator: &T::AccountId, reward: BalanceOf<T>) {
+            let mut maybe_info = Candidates::<T>::get(collator);
+            let delegator_reward = match &maybe_info {
+                Some(info) if !info.delegated.is_zero() => {
+                    let after_commission = info.commission.left_from_one() * reward;
+                    FixedU128::saturating_from_rational(info.delegated, info.total_stake())
+                        .saturating_mul_int(after_commission)
+                }
+                // A collator that left in the meantime has no delegators anymore.
+                _ => Zero::zero(),
+            };
+            let collator_reward = reward.saturating_sub(delegator_reward);
+
+            if let Err(e) =
+                T::Currency::transfer(pot, collator, collator_reward, Preservation::Preserve)
+            {
+                log::warn!(
+                    target: LOG_TARGET,
+                    "failed to pay {:?} to collator {:?}: {:?}",
+                    collator_reward,
+                    collator,
+                    e,
+                );
+            }
+            if let Some(info) = maybe_info.as_mut() {
+                if !delegator_reward.is_zero() {
+                    info.reward_per_stake =
+                        info.reward_per_stake
+                            .saturating_add(FixedU128::saturating_from_rational(
+                                delegator_reward,
+                                info.delegated,
+                            ));
+                    UnclaimedRewards::<T>::mutate(|unclaimed| {
+                        unclaimed.saturating_accrue(delegator_reward)
+                    });
+                    Candidates::<T>::insert(collator, info);
+                }
+            }
+
+            Self::deposit_event(Event::CollatorRewarded {
+                collator: collator.clone(),
+                collator_reward,
+                delegator_reward,
+            });
+        }
+    }
+
+    /// Keep track of the number of blocks authored by each collator.
+    impl<T: Config> pallet_authorship::EventHandler<T::AccountId, BlockNumberFor<T>> for Pallet<T> {
+        fn note_author(author: T::AccountId) {
+            BlocksAuthored::<T>::mutate(author, |blocks| blocks.saturating_inc());
+
+            frame_system::Pallet::<T>::register_extra_weight_unchecked(
+                T::WeightInfo::note_author(),
+                DispatchClass::Mandatory,
+            );
+        }
+    }
+
+    /// Play the role of the session manager.
+    impl<T: Config> SessionManager<T::AccountId> for Pallet<T> {
+        fn new_session(index: SessionIndex) -> Option<Vec<T::AccountId>> {
+            log::info!(
+                target: LOG_TARGET,
+                "assembling new collators for new session {} at #{:?}",
+                index,
+                <frame_system::Pallet<T>>::block_number(),
+            );
+
+            let collators = Self::select_collators();
+            frame_system::Pallet::<T>::register_extra_weight_unchecked(
+                T::WeightInfo::new_session(Candidates::<T>::count()),
+                DispatchClass::Mandatory,
+            );
+            if collators.is_empty() {
+                // Keep the current collators rather than stalling the chain.
+                return None;
+            }
+
+            Self::deposit_event(Event::CollatorsSelected {
+                session: index,
+                collators: collators.clone(),
+            });
+            Some(collators)
+        }
+
+        fn start_session(index: SessionIndex) {
+            CurrentSession::<T>::put(index);
+        }
+
+        fn end_session(_: SessionIndex) {
+            let rewarded = Self::distribute_rewards();
+            frame_system::Pallet::<T>::register_extra_weight_unchecked(
+                T::WeightInfo::end_session(rewarded),
+                DispatchClass::Mandatory,
+            );
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use super::*;
+use crate as collator_staking;
+use frame_support::{
+    derive_impl, parameter_types,
+    traits::{fungible::Mutate, ConstU32, ConstU64, OnInitialize},
+    PalletId,
+};
+use pallet_authorship::EventHandler;
+use sp_runtime::{
+    testing::UintAuthorityId,
+    traits::{ConvertInto, OpaqueKeys},
+    BuildStorage, RuntimeAppPublic,
+};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+    pub enum Test
+    {
+        System: frame_system,
+        Balances: pallet_balances,
+        CollatorStaking: collator_staking,
+        Session: pallet_session,
+    }
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+    type Block = Block;
+    type AccountData = pallet_balances::AccountData<u64>;
+}
+
+impl pallet_balances::Config for Test {
+    type Balance = u64;
+    type RuntimeEvent = RuntimeEvent;
+    type DustRemoval = ();
+    type ExistentialDeposit = ConstU64<1>;
+    type AccountStore = System;
+    type WeightInfo = ();
+    type MaxLocks = ();
+    type MaxReserves = ();
+    type ReserveIdentifier = [u8; 8];
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type RuntimeFreezeReason = RuntimeFreezeReason;
+    type FreezeIdentifier = ();
+    type MaxFreezes = ConstU32<0>;
+}
+
+sp_runtime::impl_opaque_keys! {
+    pub struct MockSessionKeys {
+        pub aura: UintAuthorityId,
+    }
+}
+
+pub struct TestSessionHandler;
+impl pallet_session::SessionHandler<u64> for TestSessionHandler {
+    const KEY_TYPE_IDS: &'static [sp_runtime::KeyTypeId] = &[UintAuthorityId::ID];
+    fn on_genesis_session<Ks: OpaqueKeys>(_: &[(u64, Ks)]) {}
+    fn on_new_session<Ks: OpaqueKeys>(_: bool, _: &[(u64, Ks)], _: &[(u64, Ks)]) {}
+    fn on_before_session_ending() {}
+    fn on_disabled(_: u32) {}
+}
+
+parameter_types! {
+    pub const Offset: u64 = 0;
+    pub const Period: u64 = 10;
+}
+
+impl pallet_session::Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type ValidatorId = <Self as frame_system::Config>::AccountId;
+    type ValidatorIdOf = ConvertInto;
+    type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
+    type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
+    type SessionManager = CollatorStaking;
+    type SessionHandler = TestSessionHandler;
+    type Keys = MockSessionKeys;
+    type WeightInfo = ();
+}
+
+parameter_types! {
+    pub const PotId: PalletId = PalletId(*b"PotStake");
+    pub const Treasury: u64 = 999;
+}
+
+/// The minimum candidate bond.
+pub const MIN_BOND: u64 = 10;
+/// The minimum delegation.
+pub const MIN_DELEGATION: u64 = 5;
+/// What every account starts with.
+pub const INITIAL_BALANCE: u64 = 1_000;
+/// An account with funds but without session keys.
+pub const NO_KEYS: u64 = 100;
+
+impl Config for Test {
+    type RuntimeEvent = RuntimeEvent;
+    type Currency = Balances;
+    type RuntimeHoldReason = RuntimeHoldReason;
+    type PotId = PotId;
+    type MaxCandidates = ConstU32<5>;
+    type MinEligibleCollators = ConstU32<2>;
+    type DesiredCollators = ConstU32<3>;
+    type MinCandidateBond = ConstU64<MIN_BOND>;
+    type MinDelegation = ConstU64<MIN_DELEGATION>;
+    type MaxDelegatorsPerCandidate = ConstU32<4>;
+    type MaxDelegationsPerDelegator = ConstU32<2>;
+    type UnbondingSessions = ConstU32<2>;
+    type MaxUnbondingChunks = ConstU32<2>;
+    type ValidatorRegistration = Session;
+    type SlashBeneficiary = Treasury;
+    type WeightInfo = ();
+}
+
+/// Externalities with accounts 1 to 20 funded and holding session keys, and 1 and 2 as
+/// candidates bonding `MIN_BOND`.
+pub fn new_test_ext() -> sp_io::TestExternalities {
+    build_ext(vec![(1, MIN_BOND), (2, MIN_BOND)])
+}
+
+/// Externalities without candidates, for the benchmarks that fill up the candidate list.
+#[cfg(feature = "runtime-benchmarks")]
+pub fn new_bench_ext() -> sp_io::TestExternalities {
+    build_ext(vec![])
+}
+
+fn build_ext(candidates: Vec<(u64, u64)>) -> sp_io::TestExternalities {
+    let mut t = frame_system::GenesisConfig::<Test>::default()
+        .build_storage()
+        .unwrap();
+    let accounts = (1..=20).collect::<Vec<u64>>();
+
+    pallet_balances::GenesisConfig::<Test> {
+        balances: accounts
+            .iter()
+            .chain([NO_KEYS].iter())
+            .map(|&who| (who, INITIAL_BALANCE))
+            .collect(),
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+    // The candidates must be known before the session pallet asks for the first collators.
+    collator_staking::GenesisConfig::<Test> { candidates }
+        .assimilate_storage(&mut t)
+        .unwrap();
+    pallet_session::GenesisConfig::<Test> {
+        keys: accounts
+            .iter()
+            .map(|&who| {
+                (
+                    who,
+                    who,
+                    MockSessionKeys {
+                        aura: UintAuthorityId(who),
+                    },
+                )
+            })
+            .collect(),
+    }
+    .assimilate_storage(&mut t)
+    .unwrap();
+
+    let mut ext = sp_io::TestExternalities::new(t);
+    ext.execute_with(|| System::set_block_number(1));
+    ext
+}
+
+/// Run blocks until the start of the next session.
+pub fn roll_session() {
+    let next = (System::block_number() / Period::get() + 1) * Period::get();
+    for n in System::block_number() + 1..=next {
+        System::set_block_number(n);
+        <AllPalletsWithSystem as OnInitialize<u64>>::on_initialize(n);
+    }
+}
+
+/// Note `blocks` blocks authored by `author` in the current session.
+pub fn author(author: u64, blocks: u32) {
+    for _ in 0..blocks {
+        <CollatorStaking as EventHandler<_, _>>::note_author(author);
+    }
+}
+
+/// Add `amount` to the rewards in the pot.
+pub fn fund_pot(amount: u64) {
+    Balances::mint_into(&CollatorStaking::account_id(), amount).unwrap();
+}
+
+/// The events deposited by the pallet, oldest first.
+pub fn staking_events() -> Vec<Event<Test>> {
+    System::events()
+        .into_iter()
+        .filter_map(|record| match record.event {
+            RuntimeEvent::CollatorStaking(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
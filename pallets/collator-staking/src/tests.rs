@@ -0,0 +1,652 @@
+use crate::{self as collator_staking, mock::*, *};
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::{
+        fungible::{Inspect, InspectHold, Mutate},
+        tokens::Preservation,
+    },
+};
+use sp_runtime::{FixedU128, Perbill, TokenError};
+
+fn hold_of(who: u64) -> u64 {
+    Balances::balance_on_hold(&HoldReason::Staking.into(), &who)
+}
+
+fn register(who: u64, bond: u64, commission: Perbill) {
+    assert_ok!(CollatorStaking::register_as_candidate(
+        RuntimeOrigin::signed(who),
+        bond,
+        commission,
+    ));
+}
+
+fn delegate(delegator: u64, candidate: u64, amount: u64) {
+    assert_ok!(CollatorStaking::delegate(
+        RuntimeOrigin::signed(delegator),
+        candidate,
+        amount,
+    ));
+}
+
+fn pending(delegator: u64, candidate: u64) -> u64 {
+    let info = Candidates::<Test>::get(candidate).unwrap();
+    let delegation = Delegations::<Test>::get(candidate, delegator).unwrap();
+    CollatorStaking::pending_rewards(&info, &delegation)
+}
+
+/// The pot must always be able to pay out every delegator's unclaimed rewards.
+fn assert_pot_covers_unclaimed() {
+    let pot = CollatorStaking::account_id();
+    assert!(
+        Balances::reducible_balance(
+            &pot,
+            Preservation::Preserve,
+            frame_support::traits::tokens::Fortitude::Polite
+        ) >= UnclaimedRewards::<Test>::get()
+    );
+}
+
+#[test]
+fn genesis_candidates_are_bonded() {
+    new_test_ext().execute_with(|| {
+        assert_eq!(Candidates::<Test>::count(), 2);
+        assert_eq!(Candidates::<Test>::get(1).unwrap().bond, MIN_BOND);
+        assert_eq!(hold_of(1), MIN_BOND);
+        assert_eq!(pallet_session::Validators::<Test>::get(), vec![1, 2]);
+        // The pot exists so rewards can be paid out while keeping it alive.
+        assert_eq!(Balances::balance(&CollatorStaking::account_id()), 1);
+    });
+}
+
+#[test]
+fn register_as_candidate_works() {
+    new_test_ext().execute_with(|| {
+        register(3, 20, Perbill::from_percent(10));
+
+        assert_eq!(
+            Candidates::<Test>::get(3),
+            Some(CandidateInfo {
+                bond: 20,
+                commission: Perbill::from_percent(10),
+                ..Default::default()
+            })
+        );
+        assert_eq!(hold_of(3), 20);
+        assert_eq!(Balances::balance(&3), INITIAL_BALANCE - 20);
+        assert_eq!(
+            staking_events().last(),
+            Some(&Event::CandidateRegistered {
+                candidate: 3,
+                bond: 20,
+                commission: Perbill::from_percent(10),
+            })
+        );
+    });
+}
+
+#[test]
+fn register_as_candidate_checks() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            CollatorStaking::register_as_candidate(RuntimeOrigin::signed(1), 20, Perbill::zero()),
+            Error::<Test>::AlreadyCandidate
+        );
+        assert_noop!(
+            CollatorStaking::register_as_candidate(
+                RuntimeOrigin::signed(3),
+                MIN_BOND - 1,
+                Perbill::zero()
+            ),
+            Error::<Test>::BondTooLow
+        );
+        assert_noop!(
+            CollatorStaking::register_as_candidate(
+                RuntimeOrigin::signed(NO_KEYS),
+                MIN_BOND,
+                Perbill::zero()
+            ),
+            Error::<Test>::ValidatorNotRegistered
+        );
+        assert_noop!(
+            CollatorStaking::register_as_candidate(
+                RuntimeOrigin::signed(3),
+                INITIAL_BALANCE + 1,
+                Perbill::zero()
+            ),
+            TokenError::FundsUnavailable
+        );
+    });
+}
+
+#[test]
+fn max_candidates_is_enforced() {
+    new_test_ext().execute_with(|| {
+        for who in 3..=5 {
+            register(who, MIN_BOND, Perbill::zero());
+        }
+        assert_eq!(Candidates::<Test>::count(), 5);
+
+        assert_noop!(
+            CollatorStaking::register_as_candidate(
+                RuntimeOrigin::signed(6),
+                MIN_BOND,
+                Perbill::zero()
+            ),
+            Error::<Test>::TooManyCandidates
+        );
+
+        // A candidate leaving makes room for another.
+        assert_ok!(CollatorStaking::leave_candidates(RuntimeOrigin::signed(5), 0));
+        register(6, MIN_BOND, Perbill::zero());
+    });
+}
+
+#[test]
+fn candidate_bond_more_works() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(CollatorStaking::candidate_bond_more(RuntimeOrigin::signed(1), 15));
+
+        assert_eq!(Candidates::<Test>::get(1).unwrap().bond, MIN_BOND + 15);
+        assert_eq!(hold_of(1), MIN_BOND + 15);
+        assert_noop!(
+            CollatorStaking::candidate_bond_more(RuntimeOrigin::signed(3), 15),
+            Error::<Test>::NotCandidate
+        );
+    });
+}
+
+#[test]
+fn candidate_bond_less_works() {
+    new_test_ext().execute_with(|| {
+        assert_ok!(CollatorStaking::candidate_bond_more(RuntimeOrigin::signed(1), 15));
+        assert_ok!(CollatorStaking::candidate_bond_less(RuntimeOrigin::signed(1), 5));
+
+        assert_eq!(Candidates::<Test>::get(1).unwrap().bond, MIN_BOND + 10);
+        // Unbonded funds stay on hold until they are withdrawn.
+        assert_eq!(hold_of(1), MIN_BOND + 15);
+        assert_eq!(
+            Unbonding::<Test>::get(1).into_inner(),
+            vec![UnbondingChunk {
+                amount: 5,
+                unlock_at: 2
+            }]
+        );
+
+        assert_noop!(
+            CollatorStaking::candidate_bond_less(RuntimeOrigin::signed(1), 11),
+            Error::<Test>::BondTooLow
+        );
+        assert_noop!(
+            CollatorStaking::candidate_bond_less(RuntimeOrigin::signed(1), 100),
+            Error::<Test>::InsufficientStake
+        );
+    });
+}
+
+#[test]
+fn leave_candidates_unbonds_bond_and_delegations() {
+    new_test_ext().execute_with(|| {
+        register(3, 20, Perbill::zero());
+        delegate(10, 3, 10);
+        delegate(11, 3, 20);
+
+        assert_noop!(
+            CollatorStaking::leave_candidates(RuntimeOrigin::signed(3), 1),
+            Error::<Test>::InvalidDelegatorCount
+        );
+        assert_ok!(CollatorStaking::leave_candidates(RuntimeOrigin::signed(3), 2));
+
+        assert!(!Candidates::<Test>::contains_key(3));
+        assert_eq!(Delegations::<Test>::iter_prefix(3).count(), 0);
+        assert!(!DelegatorTargets::<Test>::contains_key(10));
+        for (who, amount) in [(3, 20), (10, 10), (11, 20)] {
+            assert_eq!(hold_of(who), amount);
+            assert_eq!(
+                Unbonding::<Test>::get(who).into_inner(),
+                vec![UnbondingChunk {
+                    amount,
+                    unlock_at: 2
+                }]
+            );
+        }
+        assert_eq!(
+            staking_events().last(),
+            Some(&Event::CandidateLeft { candidate: 3 })
+        );
+    });
+}
+
+#[test]
+fn min_eligible_collators_is_enforced() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            CollatorStaking::leave_candidates(RuntimeOrigin::signed(1), 0),
+            Error::<Test>::TooFewEligibleCollators
+        );
+
+        register(3, MIN_BOND, Perbill::zero());
+        assert_ok!(CollatorStaking::leave_candidates(RuntimeOrigin::signed(1), 0));
+        assert_noop!(
+            CollatorStaking::leave_candidates(RuntimeOrigin::signed(2), 0),
+            Error::<Test>::TooFewEligibleCollators
+        );
+    });
+}
+
+#[test]
+fn delegate_works() {
+    new_test_ext().execute_with(|| {
+        delegate(10, 1, 20);
+        delegate(10, 1, 5);
+
+        assert_eq!(
+            Delegations::<Test>::get(1, 10),
+            Some(Delegation {
+                amount: 25,
+                reward_debt: 0
+            })
+        );
+        let info = Candidates::<Test>::get(1).unwrap();
+        assert_eq!((info.delegated, info.delegators), (25, 1));
+        assert_eq!(info.total_stake(), MIN_BOND + 25);
+        assert_eq!(DelegatorTargets::<Test>::get(10).into_inner(), vec![1]);
+        assert_eq!(hold_of(10), 25);
+    });
+}
+
+#[test]
+fn delegate_checks() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            CollatorStaking::delegate(RuntimeOrigin::signed(1), 1, 20),
+            Error::<Test>::CannotDelegateSelf
+        );
+        assert_noop!(
+            CollatorStaking::delegate(RuntimeOrigin::signed(10), 3, 20),
+            Error::<Test>::NotCandidate
+        );
+        assert_noop!(
+            CollatorStaking::delegate(RuntimeOrigin::signed(10), 1, MIN_DELEGATION - 1),
+            Error::<Test>::DelegationTooLow
+        );
+
+        for delegator in 10..14 {
+            delegate(delegator, 1, MIN_DELEGATION);
+        }
+        assert_noop!(
+            CollatorStaking::delegate(RuntimeOrigin::signed(14), 1, MIN_DELEGATION),
+            Error::<Test>::TooManyDelegators
+        );
+
+        register(3, MIN_BOND, Perbill::zero());
+        delegate(10, 2, MIN_DELEGATION);
+        assert_noop!(
+            CollatorStaking::delegate(RuntimeOrigin::signed(10), 3, MIN_DELEGATION),
+            Error::<Test>::TooManyDelegations
+        );
+    });
+}
+
+#[test]
+fn undelegate_works() {
+    new_test_ext().execute_with(|| {
+        delegate(10, 1, 20);
+
+        assert_noop!(
+            CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 21),
+            Error::<Test>::InsufficientStake
+        );
+        // What remains must be zero or at least the minimum.
+        assert_noop!(
+            CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 16),
+            Error::<Test>::DelegationTooLow
+        );
+
+        assert_ok!(CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 15));
+        assert_eq!(Delegations::<Test>::get(1, 10).unwrap().amount, 5);
+        assert_eq!(Candidates::<Test>::get(1).unwrap().delegated, 5);
+
+        assert_ok!(CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 5));
+        assert!(!Delegations::<Test>::contains_key(1, 10));
+        assert!(!DelegatorTargets::<Test>::contains_key(10));
+        let info = Candidates::<Test>::get(1).unwrap();
+        assert_eq!((info.delegated, info.delegators), (0, 0));
+        // Both parts are merged into one chunk since they unlock in the same session.
+        assert_eq!(
+            Unbonding::<Test>::get(10).into_inner(),
+            vec![UnbondingChunk {
+                amount: 20,
+                unlock_at: 2
+            }]
+        );
+        assert_eq!(hold_of(10), 20);
+
+        assert_noop!(
+            CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 5),
+            Error::<Test>::NotDelegating
+        );
+    });
+}
+
+#[test]
+fn withdraw_unbonded_waits_for_unbonding_sessions() {
+    new_test_ext().execute_with(|| {
+        delegate(10, 1, 20);
+        assert_ok!(CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 10));
+
+        // Unbonded in session 0, withdrawable from session 2.
+        assert_noop!(
+            CollatorStaking::withdraw_unbonded(RuntimeOrigin::signed(10)),
+            Error::<Test>::NothingToWithdraw
+        );
+        roll_session();
+        assert_eq!(CurrentSession::<Test>::get(), 1);
+        // A second chunk, unlocking a session later.
+        assert_ok!(CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 5));
+        assert_noop!(
+            CollatorStaking::withdraw_unbonded(RuntimeOrigin::signed(10)),
+            Error::<Test>::NothingToWithdraw
+        );
+
+        roll_session();
+        assert_ok!(CollatorStaking::withdraw_unbonded(RuntimeOrigin::signed(10)));
+        assert_eq!(
+            staking_events().last(),
+            Some(&Event::Withdrawn {
+                who: 10,
+                amount: 10
+            })
+        );
+        assert_eq!(hold_of(10), 10);
+        assert_eq!(Balances::balance(&10), INITIAL_BALANCE - 10);
+
+        roll_session();
+        assert_ok!(CollatorStaking::withdraw_unbonded(RuntimeOrigin::signed(10)));
+        assert_eq!(hold_of(10), 5);
+        assert!(!Unbonding::<Test>::contains_key(10));
+    });
+}
+
+#[test]
+fn max_unbonding_chunks_is_enforced() {
+    new_test_ext().execute_with(|| {
+        delegate(10, 1, 30);
+        assert_ok!(CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 5));
+        roll_session();
+        assert_ok!(CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 5));
+        roll_session();
+
+        assert_noop!(
+            CollatorStaking::undelegate(RuntimeOrigin::signed(10), 1, 5),
+            Error::<Test>::TooManyUnbondingChunks
+        );
+    });
+}
+
+#[test]
+fn select_collators_ranks_by_total_stake() {
+    new_test_ext().execute_with(|| {
+        register(3, 15, Perbill::zero());
+        register(4, 12, Perbill::zero());
+        delegate(10, 2, 10);
+
+        assert_eq!(CollatorStaking::select_collators(), vec![2, 3, 4]);
+
+        // The collators selected now take over from the session after next.
+        roll_session();
+        roll_session();
+        assert_eq!(pallet_session::Validators::<Test>::get(), vec![2, 3, 4]);
+    });
+}
+
+#[test]
+fn rewards_are_shared_by_blocks_and_stake() {
+    new_test_ext().execute_with(|| {
+        register(3, 20, Perbill::from_percent(20));
+        delegate(10, 3, 20);
+        author(3, 1);
+        author(1, 1);
+        fund_pot(200);
+
+        roll_session();
+
+        // 3 gets half the pot and keeps 20% of it, the rest is shared by stake: 20 out of 40.
+        assert!(staking_events().contains(&Event::CollatorRewarded {
+            collator: 3,
+            collator_reward: 60,
+            delegator_reward: 40,
+        }));
+        assert!(staking_events().contains(&Event::CollatorRewarded {
+            collator: 1,
+            collator_reward: 100,
+            delegator_reward: 0,
+        }));
+        assert_eq!(Balances::balance(&3), INITIAL_BALANCE - 20 + 60);
+        assert_eq!(Balances::balance(&1), INITIAL_BALANCE - MIN_BOND + 100);
+        assert_eq!(pending(10, 3), 40);
+        assert_eq!(UnclaimedRewards::<Test>::get(), 40);
+        assert_eq!(BlocksAuthored::<Test>::iter().count(), 0);
+        assert_pot_covers_unclaimed();
+    });
+}
+
+#[test]
+fn reward_accounting_across_sessions() {
+    new_test_ext().execute_with(|| {
+        register(3, 20, Perbill::from_percent(20));
+        delegate(10, 3, 20);
+
+        // Session 0: 3 authors every block.
+        author(3, 1);
+        fund_pot(100);
+        roll_session();
+        let info = Candidates::<Test>::get(3).unwrap();
+        assert_eq!(info.reward_per_stake, FixedU128::from_u32(2));
+        assert_eq!(pending(10, 3), 40);
+
+        // Session 1: 11 joins, and is not owed anything from session 0.
+        delegate(11, 3, 40);
+        assert_eq!(
+            Delegations::<Test>::get(3, 11).unwrap().reward_debt,
+            2 * 40
+        );
+        assert_eq!(pending(11, 3), 0);
+        author(3, 1);
+        author(1, 1);
+        fund_pot(160);
+        roll_session();
+
+        // 3 gets 80 and keeps 20% of it, 48 of the 64 left is shared by the 60 delegated.
+        let info = Candidates::<Test>::get(3).unwrap();
+        assert_eq!(
+            info.reward_per_stake,
+            FixedU128::from_rational(28, 10)
+        );
+        assert_eq!(pending(10, 3), 40 + 16);
+        assert_eq!(pending(11, 3), 32);
+        assert_eq!(UnclaimedRewards::<Test>::get(), 88);
+        assert_pot_covers_unclaimed();
+
+        // Claiming pays out and resets the debt.
+        assert_ok!(CollatorStaking::claim_rewards(RuntimeOrigin::signed(10), 3));
+        assert_eq!(Balances::balance(&10), INITIAL_BALANCE - 20 + 56);
+        assert_eq!(pending(10, 3), 0);
+        assert_eq!(UnclaimedRewards::<Test>::get(), 32);
+        assert_pot_covers_unclaimed();
+
+        // Session 2: nothing is authored, nothing accrues.
+        roll_session();
+        assert_eq!(pending(10, 3), 0);
+        assert_eq!(pending(11, 3), 32);
+
+        // Topping up pays out what is pending first.
+        delegate(11, 3, 10);
+        assert_eq!(Balances::balance(&11), INITIAL_BALANCE - 50 + 32);
+        assert_eq!(pending(11, 3), 0);
+        assert_eq!(UnclaimedRewards::<Test>::get(), 0);
+
+        // Only the existential deposit is left in the pot.
+        assert_eq!(Balances::balance(&CollatorStaking::account_id()), 1);
+    });
+}
+
+#[test]
+fn unclaimed_rewards_are_not_paid_out_again() {
+    new_test_ext().execute_with(|| {
+        delegate(10, 1, 30);
+        author(1, 1);
+        fund_pot(80);
+        roll_session();
+        // 30 of the 40 staked behind 1 is delegated.
+        assert_eq!(UnclaimedRewards::<Test>::get(), 60);
+
+        // The next session's rewards come out of what is left, not out of the unclaimed part.
+        author(2, 1);
+        fund_pot(10);
+        roll_session();
+        assert!(staking_events().contains(&Event::CollatorRewarded {
+            collator: 2,
+            collator_reward: 10,
+            delegator_reward: 0,
+        }));
+        assert_eq!(UnclaimedRewards::<Test>::get(), 60);
+        assert_pot_covers_unclaimed();
+
+        assert_ok!(CollatorStaking::claim_rewards(RuntimeOrigin::signed(10), 1));
+        assert_eq!(UnclaimedRewards::<Test>::get(), 0);
+        assert_eq!(Balances::balance(&CollatorStaking::account_id()), 1);
+    });
+}
+
+#[test]
+fn leaving_pays_out_pending_rewards() {
+    new_test_ext().execute_with(|| {
+        register(3, 20, Perbill::zero());
+        delegate(10, 3, 20);
+        author(3, 1);
+        fund_pot(40);
+        roll_session();
+        assert_eq!(pending(10, 3), 20);
+
+        assert_ok!(CollatorStaking::leave_candidates(RuntimeOrigin::signed(3), 1));
+
+        assert_eq!(Balances::balance(&10), INITIAL_BALANCE - 20 + 20);
+        assert_eq!(UnclaimedRewards::<Test>::get(), 0);
+        assert_pot_covers_unclaimed();
+    });
+}
+
+#[test]
+fn leaving_forfeits_rewards_that_cannot_be_paid() {
+    new_test_ext().execute_with(|| {
+        register(3, 20, Perbill::zero());
+        delegate(10, 3, 20);
+        author(3, 1);
+        fund_pot(40);
+        roll_session();
+        assert_eq!(UnclaimedRewards::<Test>::get(), 20);
+
+        // The pot can't pay out anything.
+        Balances::set_balance(&CollatorStaking::account_id(), 1);
+        assert_ok!(CollatorStaking::leave_candidates(RuntimeOrigin::signed(3), 1));
+
+        assert_eq!(Balances::balance(&10), INITIAL_BALANCE - 20);
+        // Nothing is reserved for the removed delegation anymore.
+        assert_eq!(UnclaimedRewards::<Test>::get(), 0);
+    });
+}
+
+#[test]
+fn claim_rewards_checks() {
+    new_test_ext().execute_with(|| {
+        assert_noop!(
+            CollatorStaking::claim_rewards(RuntimeOrigin::signed(10), 1),
+            Error::<Test>::NotDelegating
+        );
+
+        // Claiming nothing is fine.
+        delegate(10, 1, 20);
+        assert_ok!(CollatorStaking::claim_rewards(RuntimeOrigin::signed(10), 1));
+        assert_eq!(Balances::balance(&10), INITIAL_BALANCE - 20);
+    });
+}
+
+#[test]
+fn slash_takes_from_the_bond_only() {
+    new_test_ext().execute_with(|| {
+        register(3, 100, Perbill::zero());
+        delegate(10, 3, 50);
+
+        assert_eq!(CollatorStaking::slash(&3, Perbill::from_percent(10)), 10);
+
+        assert_eq!(Candidates::<Test>::get(3).unwrap().bond, 90);
+        assert_eq!(hold_of(3), 90);
+        assert_eq!(hold_of(10), 50);
+        assert_eq!(Balances::balance(&Treasury::get()), 10);
+    });
+}
+
+#[test]
+fn slash_takes_from_funds_unbonded_as_a_candidate() {
+    new_test_ext().execute_with(|| {
+        register(3, 100, Perbill::zero());
+        assert_ok!(CollatorStaking::candidate_bond_less(
+            RuntimeOrigin::signed(3),
+            80
+        ));
+
+        // 10% of the 20 bonded and of the 80 unbonding.
+        assert_eq!(CollatorStaking::slash(&3, Perbill::from_percent(10)), 10);
+
+        assert_eq!(Candidates::<Test>::get(3).unwrap().bond, 18);
+        assert_eq!(Unbonding::<Test>::get(3)[0].amount, 72);
+        assert_eq!(hold_of(3), 90);
+        assert_eq!(Balances::balance(&Treasury::get()), 10);
+    });
+}
+
+#[test]
+fn slashed_below_the_minimum_bond_is_not_selected() {
+    new_test_ext().execute_with(|| {
+        delegate(10, 1, 20);
+
+        assert_eq!(CollatorStaking::slash(&1, Perbill::from_percent(10)), 1);
+
+        // The candidate and their delegators stay, but they aren't selected anymore.
+        assert_eq!(Candidates::<Test>::get(1).unwrap().bond, MIN_BOND - 1);
+        assert_eq!(Delegations::<Test>::get(1, 10).unwrap().amount, 20);
+        assert_eq!(hold_of(10), 20);
+        assert_eq!(CollatorStaking::select_collators(), vec![2]);
+
+        assert_ok!(CollatorStaking::candidate_bond_more(
+            RuntimeOrigin::signed(1),
+            1
+        ));
+        assert_eq!(CollatorStaking::select_collators(), vec![1, 2]);
+    });
+}
+
+#[test]
+fn slash_takes_from_unbonding_funds_after_leaving() {
+    new_test_ext().execute_with(|| {
+        register(3, 100, Perbill::zero());
+        assert_ok!(CollatorStaking::leave_candidates(RuntimeOrigin::signed(3), 0));
+
+        assert_eq!(CollatorStaking::slash(&3, Perbill::from_percent(10)), 10);
+
+        assert_eq!(Unbonding::<Test>::get(3)[0].amount, 90);
+        assert_eq!(hold_of(3), 90);
+    });
+}
+
+#[test]
+fn collator_staking_is_the_session_manager() {
+    new_test_ext().execute_with(|| {
+        roll_session();
+        assert!(staking_events().contains(&Event::CollatorsSelected {
+            session: 2,
+            collators: vec![1, 2],
+        }));
+        assert_eq!(collator_staking::CurrentSession::<Test>::get(), 1);
+    });
+}
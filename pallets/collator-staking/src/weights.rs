@@ -0,0 +1,99 @@
+//! Weights for `pallet_collator_staking`.
+//!
+//! The `()` implementation estimates each call from its storage accesses and is meant for tests.
+//! Runtimes should use weights generated from the pallet's benchmarks.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+
+/// Weight functions needed for `pallet_collator_staking`.
+pub trait WeightInfo {
+    fn register_as_candidate() -> Weight;
+    fn candidate_bond_more() -> Weight;
+    fn candidate_bond_less() -> Weight;
+    fn set_commission() -> Weight;
+    fn leave_candidates(d: u32) -> Weight;
+    fn delegate() -> Weight;
+    fn undelegate() -> Weight;
+    fn claim_rewards() -> Weight;
+    fn withdraw_unbonded() -> Weight;
+    fn note_author() -> Weight;
+    fn new_session(c: u32) -> Weight;
+    fn end_session(r: u32) -> Weight;
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+    fn register_as_candidate() -> Weight {
+        Weight::from_parts(60_000_000, 4_000)
+            .saturating_add(RocksDbWeight::get().reads(4_u64))
+            .saturating_add(RocksDbWeight::get().writes(4_u64))
+    }
+    fn candidate_bond_more() -> Weight {
+        Weight::from_parts(50_000_000, 4_000)
+            .saturating_add(RocksDbWeight::get().reads(3_u64))
+            .saturating_add(RocksDbWeight::get().writes(3_u64))
+    }
+    fn candidate_bond_less() -> Weight {
+        Weight::from_parts(40_000_000, 4_000)
+            .saturating_add(RocksDbWeight::get().reads(3_u64))
+            .saturating_add(RocksDbWeight::get().writes(2_u64))
+    }
+    fn set_commission() -> Weight {
+        Weight::from_parts(20_000_000, 4_000)
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+    fn leave_candidates(d: u32) -> Weight {
+        Weight::from_parts(50_000_000, 4_000)
+            .saturating_add(Weight::from_parts(60_000_000, 8_000).saturating_mul(d.into()))
+            .saturating_add(RocksDbWeight::get().reads(4_u64))
+            .saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(d.into())))
+            .saturating_add(RocksDbWeight::get().writes(4_u64))
+            .saturating_add(RocksDbWeight::get().writes((5_u64).saturating_mul(d.into())))
+    }
+    fn delegate() -> Weight {
+        Weight::from_parts(90_000_000, 8_000)
+            .saturating_add(RocksDbWeight::get().reads(7_u64))
+            .saturating_add(RocksDbWeight::get().writes(7_u64))
+    }
+    fn undelegate() -> Weight {
+        Weight::from_parts(90_000_000, 8_000)
+            .saturating_add(RocksDbWeight::get().reads(7_u64))
+            .saturating_add(RocksDbWeight::get().writes(7_u64))
+    }
+    fn claim_rewards() -> Weight {
+        Weight::from_parts(60_000_000, 8_000)
+            .saturating_add(RocksDbWeight::get().reads(5_u64))
+            .saturating_add(RocksDbWeight::get().writes(4_u64))
+    }
+    fn withdraw_unbonded() -> Weight {
+        Weight::from_parts(50_000_000, 4_000)
+            .saturating_add(RocksDbWeight::get().reads(3_u64))
+            .saturating_add(RocksDbWeight::get().writes(2_u64))
+    }
+    fn note_author() -> Weight {
+        Weight::from_parts(10_000_000, 3_000)
+            .saturating_add(RocksDbWeight::get().reads(1_u64))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+    }
+    fn new_session(c: u32) -> Weight {
+        Weight::from_parts(20_000_000, 4_000)
+            .saturating_add(Weight::from_parts(10_000_000, 3_000).saturating_mul(c.into()))
+            .saturating_add(RocksDbWeight::get().reads(2_u64))
+            .saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(c.into())))
+    }
+    fn end_session(r: u32) -> Weight {
+        Weight::from_parts(30_000_000, 4_000)
+            .saturating_add(Weight::from_parts(60_000_000, 8_000).saturating_mul(r.into()))
+            .saturating_add(RocksDbWeight::get().reads(3_u64))
+            .saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(r.into())))
+            .saturating_add(RocksDbWeight::get().writes(1_u64))
+            .saturating_add(RocksDbWeight::get().writes((4_u64).saturating_mul(r.into())))
+    }
+}
@@ -5,7 +5,9 @@ frame_benchmarking::define_benchmarks!(
     [pallet_timestamp, Timestamp]
     [pallet_balances, Balances]
     [pallet_sudo, Sudo]
-    [pallet_collator_selection, CollatorSelection]
+    [pallet_parameters, Parameters]
+    [pallet_collator_staking, CollatorStaking]
+    [pallet_collator_offences, CollatorOffences]
     [pallet_session, SessionBench::<Runtime>]
     [cumulus_pallet_xcmp_queue, XcmpQueue]
     [pallet_message_queue, MessageQueue]
@@ -0,0 +1,243 @@
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_support::{
+    dispatch::DispatchClass,
+    parameter_types,
+    traits::{fungible::Mutate, Get},
+    PalletId,
+};
+use scale_info::TypeInfo;
+use sp_runtime::{
+    traits::{AccountIdConversion, Zero},
+    PerThing, Perbill, RuntimeDebug,
+};
+
+use super::{dynamic_params::issuance, xcm_config::TreasuryAccount};
+use crate::{
+    weights::RocksDbWeight, AccountId, Balance, Balances, BlockNumber, CollatorStaking, System,
+    YEARS,
+};
+
+parameter_types! {
+    /// Pot that collects the share of new issuance reserved for bloc incentives.
+    pub const BlocIncentivePotId: PalletId = PalletId(*b"BlocIncv");
+}
+
+/// How many new tokens are minted with every block.
+#[derive(Clone, Copy, Encode, Decode, Eq, PartialEq, RuntimeDebug, MaxEncodedLen, TypeInfo)]
+pub enum IssuanceModel {
+    /// Inflate the total issuance by this rate over a year of blocks.
+    ///
+    /// The per-block amount is taken from the current total issuance, so it compounds: 5% a
+    /// year grows the total issuance by about 5.13%.
+    AnnualRate(Perbill),
+    /// Mint a fixed amount with every block.
+    PerBlock(Balance),
+}
+
+impl IssuanceModel {
+    /// The amount to mint for a single block, given the current total issuance.
+    pub fn per_block(&self, total_issuance: Balance) -> Balance {
+        match self {
+            IssuanceModel::AnnualRate(rate) => {
+                rate.mul_floor(total_issuance) / Balance::from(YEARS)
+            }
+            IssuanceModel::PerBlock(amount) => *amount,
+        }
+    }
+}
+
+/// Mints the block reward when the block author is noted and splits it between the collator
+/// staking pot, the treasury and the bloc incentive pot.
+///
+/// The collators' share is paid out of the pot when the session ends.
+pub struct BlockReward;
+
+impl pallet_authorship::EventHandler<AccountId, BlockNumber> for BlockReward {
+    fn note_author(_author: AccountId) {
+        let reward = issuance::Issuance::get().per_block(Balances::total_issuance());
+        let collator_share = issuance::CollatorShare::get();
+        // The treasury can never take more than what is left after the collators.
+        let treasury_share = issuance::TreasuryShare::get().min(collator_share.left_from_one());
+
+        let to_collators = collator_share * reward;
+        let to_treasury = treasury_share * reward;
+        let to_incentives = reward
+            .saturating_sub(to_collators)
+            .saturating_sub(to_treasury);
+
+        for (who, amount) in [
+            (CollatorStaking::account_id(), to_collators),
+            (TreasuryAccount::get(), to_treasury),
+            (
+                BlocIncentivePotId::get().into_account_truncating(),
+                to_incentives,
+            ),
+        ] {
+            if amount.is_zero() {
+                continue;
+            }
+            // Minting less than the existential deposit into an empty account fails, the reward
+            // is simply not issued then.
+            if let Err(e) = Balances::mint_into(&who, amount) {
+                log::warn!(
+                    target: "runtime::issuance",
+                    "failed to mint {:?} into {:?}: {:?}",
+                    amount,
+                    who,
+                    e,
+                );
+            }
+        }
+
+        // Parameter reads plus three mints, each touching an account and the total issuance.
+        System::register_extra_weight_unchecked(
+            RocksDbWeight::get().reads_writes(7, 6),
+            DispatchClass::Mandatory,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        configs::RuntimeParameters, Parameters, Runtime, RuntimeOrigin, EXISTENTIAL_DEPOSIT, UNIT,
+    };
+    use frame_support::traits::fungible::Inspect;
+    use pallet_authorship::EventHandler;
+    use sp_runtime::BuildStorage;
+
+    const INITIAL_ISSUANCE: Balance = 10_000_000 * UNIT;
+
+    fn new_test_ext() -> sp_io::TestExternalities {
+        let mut t = frame_system::GenesisConfig::<Runtime>::default()
+            .build_storage()
+            .unwrap();
+        pallet_balances::GenesisConfig::<Runtime> {
+            balances: vec![(AccountId::new([1; 32]), INITIAL_ISSUANCE)],
+        }
+        .assimilate_storage(&mut t)
+        .unwrap();
+        t.into()
+    }
+
+    fn set_issuance(model: IssuanceModel, collator_share: Perbill, treasury_share: Perbill) {
+        for parameter in [
+            issuance::Parameters::Issuance(issuance::Issuance, Some(model)),
+            issuance::Parameters::CollatorShare(issuance::CollatorShare, Some(collator_share)),
+            issuance::Parameters::TreasuryShare(issuance::TreasuryShare, Some(treasury_share)),
+        ] {
+            Parameters::set_parameter(
+                RuntimeOrigin::root(),
+                RuntimeParameters::Issuance(parameter),
+            )
+            .unwrap();
+        }
+    }
+
+    fn incentive_pot() -> AccountId {
+        BlocIncentivePotId::get().into_account_truncating()
+    }
+
+    /// Balances of the collator pot, the treasury and the bloc incentive pot.
+    fn pots() -> [Balance; 3] {
+        [
+            CollatorStaking::account_id(),
+            TreasuryAccount::get(),
+            incentive_pot(),
+        ]
+        .map(|who| Balances::balance(&who))
+    }
+
+    /// Total issuance after a year of blocks minted under `model`.
+    fn simulate_year(model: IssuanceModel) -> Balance {
+        (0..YEARS).fold(INITIAL_ISSUANCE, |total, _| total + model.per_block(total))
+    }
+
+    #[test]
+    fn annual_rate_compounds_over_a_year() {
+        let total = simulate_year(IssuanceModel::AnnualRate(Perbill::from_percent(5)));
+        let inflation = Perbill::from_rational(total - INITIAL_ISSUANCE, INITIAL_ISSUANCE);
+
+        // e^0.05 - 1, less a little for rounding down every block.
+        assert!(inflation > Perbill::from_parts(51_200_000), "{inflation:?}");
+        assert!(inflation < Perbill::from_parts(51_300_000), "{inflation:?}");
+    }
+
+    #[test]
+    fn per_block_mints_the_same_amount_every_block() {
+        let total = simulate_year(IssuanceModel::PerBlock(UNIT));
+
+        assert_eq!(total - INITIAL_ISSUANCE, Balance::from(YEARS) * UNIT);
+    }
+
+    #[test]
+    fn block_reward_is_split_between_pots() {
+        new_test_ext().execute_with(|| {
+            let reward =
+                IssuanceModel::AnnualRate(Perbill::from_percent(5)).per_block(INITIAL_ISSUANCE);
+
+            let to_collators = Perbill::from_percent(50) * reward;
+            let to_treasury = Perbill::from_percent(30) * reward;
+
+            BlockReward::note_author(AccountId::new([1; 32]));
+
+            assert_eq!(
+                pots(),
+                [
+                    to_collators,
+                    to_treasury,
+                    reward - to_collators - to_treasury
+                ]
+            );
+            assert_eq!(Balances::total_issuance(), INITIAL_ISSUANCE + reward);
+        });
+    }
+
+    #[test]
+    fn treasury_share_is_capped_by_collator_share() {
+        new_test_ext().execute_with(|| {
+            set_issuance(
+                IssuanceModel::PerBlock(100 * EXISTENTIAL_DEPOSIT),
+                Perbill::from_percent(80),
+                Perbill::from_percent(50),
+            );
+
+            BlockReward::note_author(AccountId::new([1; 32]));
+
+            // The treasury gets what is left after the collators, nothing goes to incentives.
+            assert_eq!(
+                pots(),
+                [80 * EXISTENTIAL_DEPOSIT, 20 * EXISTENTIAL_DEPOSIT, 0]
+            );
+            assert_eq!(
+                Balances::total_issuance(),
+                INITIAL_ISSUANCE + 100 * EXISTENTIAL_DEPOSIT
+            );
+        });
+    }
+
+    #[test]
+    fn mints_below_the_existential_deposit_are_skipped() {
+        new_test_ext().execute_with(|| {
+            set_issuance(
+                IssuanceModel::PerBlock(EXISTENTIAL_DEPOSIT),
+                Perbill::from_percent(50),
+                Perbill::from_percent(30),
+            );
+
+            // None of the pots exist yet, and no share is enough to create one.
+            BlockReward::note_author(AccountId::new([1; 32]));
+            assert_eq!(pots(), [0, 0, 0]);
+            assert_eq!(Balances::total_issuance(), INITIAL_ISSUANCE);
+
+            // Once a pot exists it takes any amount.
+            Balances::mint_into(&CollatorStaking::account_id(), EXISTENTIAL_DEPOSIT).unwrap();
+            BlockReward::note_author(AccountId::new([1; 32]));
+            assert_eq!(
+                pots(),
+                [EXISTENTIAL_DEPOSIT + EXISTENTIAL_DEPOSIT / 2, 0, 0]
+            );
+        });
+    }
+}
@@ -1,3 +1,4 @@
+mod issuance;
 #[path = "xcm.rs"]
 mod xcm_config;
 
@@ -7,8 +8,12 @@ use cumulus_primitives_core::{AggregateMessageOrigin, ParaId};
 use frame_support::{
     derive_impl,
     dispatch::DispatchClass,
+    dynamic_params::{dynamic_pallet_params, dynamic_params},
     parameter_types,
-    traits::{ConstBool, ConstU32, ConstU64, ConstU8, EitherOfDiverse, TransformOrigin},
+    traits::{
+        ConstBool, ConstU32, ConstU64, ConstU8, EitherOfDiverse, EnsureOrigin, EnsureOriginWithArg,
+        Get, TransformOrigin,
+    },
     weights::{ConstantMultiplier, Weight},
     PalletId,
 };
@@ -22,20 +27,22 @@ use polkadot_runtime_common::{
     xcm_sender::NoPriceForMessageDelivery, BlockHashCount, SlowAdjustingFeeUpdate,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_runtime::Perbill;
+use sp_runtime::{traits::ConvertInto, Perbill};
 use sp_version::RuntimeVersion;
 use xcm::latest::prelude::BodyId;
 
 // Local module imports
 use super::{
-    weights::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
-    AccountId, Aura, Balance, Balances, Block, BlockNumber, CollatorSelection, ConsensusHook, Hash,
+    weights::{self, BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
+    AccountId, Aura, Balance, Balances, Block, BlockNumber, CollatorStaking, ConsensusHook, Hash,
     MessageQueue, Nonce, PalletInfo, ParachainSystem, Runtime, RuntimeCall, RuntimeEvent,
     RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask, Session, SessionKeys,
-    System, WeightToFee, XcmpQueue, AVERAGE_ON_INITIALIZE_RATIO, EXISTENTIAL_DEPOSIT, HOURS,
-    MAXIMUM_BLOCK_WEIGHT, MICROUNIT, NORMAL_DISPATCH_RATIO, SLOT_DURATION, VERSION,
+    System, WeightToFee, XcmpQueue, AVERAGE_ON_INITIALIZE_RATIO, DAYS, EXISTENTIAL_DEPOSIT, HOURS,
+    MAXIMUM_BLOCK_WEIGHT, MICROUNIT, MILLIUNIT, MINUTES, NORMAL_DISPATCH_RATIO, SLOT_DURATION,
+    VERSION,
 };
-use xcm_config::{RelayLocation, XcmOriginToTransactDispatchOrigin};
+use issuance::{BlockReward, IssuanceModel};
+use xcm_config::{RelayLocation, TreasuryAccount, XcmOriginToTransactDispatchOrigin};
 
 parameter_types! {
     pub const Version: RuntimeVersion = VERSION;
@@ -109,7 +116,7 @@ impl pallet_timestamp::Config for Runtime {
 
 impl pallet_authorship::Config for Runtime {
     type FindAuthor = pallet_session::FindAccountFromAuthorIndex<Self, Aura>;
-    type EventHandler = (CollatorSelection,);
+    type EventHandler = (BlockReward, CollatorStaking);
 }
 
 parameter_types! {
@@ -134,16 +141,12 @@ impl pallet_balances::Config for Runtime {
     type MaxFreezes = ConstU32<0>;
 }
 
-parameter_types! {
-    /// Relay Chain `TransactionByteFee` / 10
-    pub const TransactionByteFee: Balance = 10 * MICROUNIT;
-}
-
 impl pallet_transaction_payment::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type OnChargeTransaction = pallet_transaction_payment::FungibleAdapter<Balances, ()>;
     type WeightToFee = WeightToFee;
-    type LengthToFee = ConstantMultiplier<Balance, TransactionByteFee>;
+    type LengthToFee =
+        ConstantMultiplier<Balance, dynamic_params::transaction_payment::TransactionByteFee>;
     type FeeMultiplierUpdate = SlowAdjustingFeeUpdate<Self>;
     type OperationalFeeMultiplier = ConstU8<5>;
 }
@@ -154,6 +157,140 @@ impl pallet_sudo::Config for Runtime {
     type WeightInfo = (); // Configure based on benchmarking results.
 }
 
+/// Economic constants that can be changed through `pallet_parameters` without a runtime upgrade.
+///
+/// Each read is a single storage lookup, falling back to the default given here when the
+/// parameter has never been set.
+#[dynamic_params(RuntimeParameters, pallet_parameters::Parameters::<Runtime>)]
+pub mod dynamic_params {
+    use super::*;
+
+    #[dynamic_pallet_params]
+    #[codec(index = 0)]
+    pub mod transaction_payment {
+        /// Fee charged for `ExtrinsicBaseWeight`, the smallest non-zero weight.
+        ///
+        /// In Rococo this is 1 MILLIUNIT, we default to 1/10 of that.
+        #[codec(index = 0)]
+        pub static ExtrinsicBaseFee: Balance = MILLIUNIT / 10;
+
+        /// Fee charged per byte of an extrinsic. Relay Chain `TransactionByteFee` / 10.
+        #[codec(index = 1)]
+        pub static TransactionByteFee: Balance = 10 * MICROUNIT;
+    }
+
+    #[dynamic_pallet_params]
+    #[codec(index = 1)]
+    pub mod session {
+        /// Length of a session, in blocks.
+        ///
+        /// Sessions end on every block that is a multiple of this value, so a change takes
+        /// effect from the next such block. Read through [`SessionPeriod`](super::SessionPeriod),
+        /// which keeps it within bounds.
+        #[codec(index = 0)]
+        pub static Period: BlockNumber = 6 * HOURS;
+    }
+
+    #[dynamic_pallet_params]
+    #[codec(index = 2)]
+    pub mod issuance {
+        /// How much is minted with every block.
+        #[codec(index = 0)]
+        pub static Issuance: IssuanceModel = IssuanceModel::AnnualRate(Perbill::from_percent(5));
+
+        /// Share of the block reward paid into the collator staking pot.
+        #[codec(index = 1)]
+        pub static CollatorShare: Perbill = Perbill::from_percent(50);
+
+        /// Share of the block reward paid into the treasury. Whatever is left after the
+        /// collators and the treasury goes to the bloc incentive pot.
+        #[codec(index = 2)]
+        pub static TreasuryShare: Perbill = Perbill::from_percent(30);
+    }
+
+    #[dynamic_pallet_params]
+    #[codec(index = 3)]
+    pub mod staking {
+        /// Number of collators selected for each session.
+        #[codec(index = 0)]
+        pub static DesiredCollators: u32 = 20;
+
+        /// Minimum bond of a collator candidate.
+        #[codec(index = 1)]
+        pub static MinCandidateBond: Balance = EXISTENTIAL_DEPOSIT * 16;
+
+        /// Minimum amount that can be delegated to a candidate.
+        #[codec(index = 2)]
+        pub static MinDelegation: Balance = EXISTENTIAL_DEPOSIT * 10;
+    }
+
+    #[dynamic_pallet_params]
+    #[codec(index = 4)]
+    pub mod offences {
+        /// Share of a collator's bond slashed for an Aura equivocation.
+        #[codec(index = 0)]
+        pub static EquivocationSlash: Perbill = Perbill::from_percent(10);
+
+        /// Number of missed slots in a session after which a collator is punished. Zero disables
+        /// the tracking of missed slots, which is the default with on-demand coretime, as blocks
+        /// are then not authored in every slot.
+        #[codec(index = 1)]
+        pub static MaxMissedSlots: u32 = if cfg!(feature = "on-demand") { 0 } else { 50 };
+
+        /// Share of a collator's bond slashed for missing `MaxMissedSlots` slots.
+        #[codec(index = 2)]
+        pub static MissedSlotsSlash: Perbill = Perbill::from_percent(1);
+    }
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+impl Default for RuntimeParameters {
+    fn default() -> Self {
+        RuntimeParameters::TransactionPayment(
+            dynamic_params::transaction_payment::Parameters::ExtrinsicBaseFee(
+                dynamic_params::transaction_payment::ExtrinsicBaseFee,
+                Some(MILLIUNIT / 10),
+            ),
+        )
+    }
+}
+
+/// Fee and issuance parameters can only be changed by root. The session period, staking and
+/// offence parameters can also be changed by the [`StakingAdminOrigin`].
+pub struct DynamicParameterOrigin;
+
+impl EnsureOriginWithArg<RuntimeOrigin, RuntimeParametersKey> for DynamicParameterOrigin {
+    type Success = ();
+
+    fn try_origin(
+        origin: RuntimeOrigin,
+        key: &RuntimeParametersKey,
+    ) -> Result<Self::Success, RuntimeOrigin> {
+        match key {
+            RuntimeParametersKey::TransactionPayment(_) | RuntimeParametersKey::Issuance(_) => {
+                <EnsureRoot<AccountId> as EnsureOrigin<_>>::try_origin(origin)
+            }
+            RuntimeParametersKey::Session(_)
+            | RuntimeParametersKey::Staking(_)
+            | RuntimeParametersKey::Offences(_) => {
+                StakingAdminOrigin::try_origin(origin).map(|_| ())
+            }
+        }
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn try_successful_origin(_key: &RuntimeParametersKey) -> Result<RuntimeOrigin, ()> {
+        Ok(RuntimeOrigin::root())
+    }
+}
+
+impl pallet_parameters::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type RuntimeParameters = RuntimeParameters;
+    type AdminOrigin = DynamicParameterOrigin;
+    type WeightInfo = weights::pallet_parameters::WeightInfo<Runtime>;
+}
+
 parameter_types! {
     pub const ReservedXcmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT.saturating_div(4);
     pub const ReservedDmpWeight: Weight = MAXIMUM_BLOCK_WEIGHT.saturating_div(4);
@@ -219,18 +356,35 @@ impl cumulus_pallet_xcmp_queue::Config for Runtime {
 }
 
 parameter_types! {
-    pub const Period: u32 = 6 * HOURS;
     pub const Offset: u32 = 0;
 }
 
+/// Shortest session period governance can set.
+pub const MIN_SESSION_PERIOD: BlockNumber = 10 * MINUTES;
+/// Longest session period governance can set.
+pub const MAX_SESSION_PERIOD: BlockNumber = DAYS;
+
+/// The session period set through `pallet_parameters`, clamped to
+/// `[MIN_SESSION_PERIOD, MAX_SESSION_PERIOD]`.
+///
+/// `PeriodicSessions` takes the block number modulo the period, so a period of zero would panic
+/// in `on_initialize` and halt the chain.
+pub struct SessionPeriod;
+
+impl Get<BlockNumber> for SessionPeriod {
+    fn get() -> BlockNumber {
+        dynamic_params::session::Period::get().clamp(MIN_SESSION_PERIOD, MAX_SESSION_PERIOD)
+    }
+}
+
 impl pallet_session::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type ValidatorId = <Self as frame_system::Config>::AccountId;
     // we don't have stash and controller, thus we don't need the convert as well.
-    type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
-    type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
-    type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
-    type SessionManager = CollatorSelection;
+    type ValidatorIdOf = ConvertInto;
+    type ShouldEndSession = pallet_session::PeriodicSessions<SessionPeriod, Offset>;
+    type NextSessionRotation = pallet_session::PeriodicSessions<SessionPeriod, Offset>;
+    type SessionManager = CollatorStaking;
     // Essentially just Aura, but let's be pedantic.
     type SessionHandler = <SessionKeys as sp_runtime::traits::OpaqueKeys>::KeyTypeIdProviders;
     type Keys = SessionKeys;
@@ -239,7 +393,7 @@ impl pallet_session::Config for Runtime {
 
 impl pallet_aura::Config for Runtime {
     type AuthorityId = AuraId;
-    type DisabledValidators = ();
+    type DisabledValidators = Session;
     type MaxAuthorities = ConstU32<100_000>;
     type AllowMultipleBlocksPerSlot = ConstBool<true>;
     type SlotDuration = ConstU64<SLOT_DURATION>;
@@ -252,24 +406,174 @@ parameter_types! {
     pub const StakingAdminBodyId: BodyId = BodyId::Defense;
 }
 
-/// We allow root and the StakingAdmin to execute privileged collator selection operations.
-pub type CollatorSelectionUpdateOrigin = EitherOfDiverse<
+/// Sessions unbonded funds stay on hold: 7 days at the default session period of 6 hours.
+///
+/// Never fewer than an equivocation can still be reported for, so that a collator can't unbond
+/// and withdraw their bond before the report against them lands. The session the funds were
+/// unbonded in counts as one, but may be about to end.
+pub struct UnbondingSessions;
+
+impl Get<u32> for UnbondingSessions {
+    fn get() -> u32 {
+        // Sessions are counted in blocks and reports in slots, both of `SLOT_DURATION`.
+        let period = u64::from(SessionPeriod::get());
+        let report_sessions = EquivocationReportLongevity::get().div_ceil(period) + 1;
+        u32::try_from(report_sessions).unwrap_or(u32::MAX).max(28)
+    }
+}
+
+/// We allow root and the StakingAdmin body to change the staking parameters.
+pub type StakingAdminOrigin = EitherOfDiverse<
     EnsureRoot<AccountId>,
     EnsureXcm<IsVoiceOfBody<RelayLocation, StakingAdminBodyId>>,
 >;
 
-impl pallet_collator_selection::Config for Runtime {
+impl pallet_collator_staking::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type Currency = Balances;
-    type UpdateOrigin = CollatorSelectionUpdateOrigin;
+    type RuntimeHoldReason = RuntimeHoldReason;
     type PotId = PotId;
     type MaxCandidates = ConstU32<100>;
     type MinEligibleCollators = ConstU32<4>;
-    type MaxInvulnerables = ConstU32<20>;
-    // should be a multiple of session or things will get inconsistent
-    type KickThreshold = Period;
-    type ValidatorId = <Self as frame_system::Config>::AccountId;
-    type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
+    type DesiredCollators = dynamic_params::staking::DesiredCollators;
+    type MinCandidateBond = dynamic_params::staking::MinCandidateBond;
+    type MinDelegation = dynamic_params::staking::MinDelegation;
+    type MaxDelegatorsPerCandidate = ConstU32<300>;
+    type MaxDelegationsPerDelegator = ConstU32<8>;
+    type UnbondingSessions = UnbondingSessions;
+    type MaxUnbondingChunks = ConstU32<16>;
     type ValidatorRegistration = Session;
-    type WeightInfo = (); // Configure based on benchmarking results.
+    type SlashBeneficiary = TreasuryAccount;
+    type WeightInfo = weights::pallet_collator_staking::WeightInfo<Runtime>;
+}
+
+/// Slashes punished collators' bonds into the treasury.
+pub struct SlashCollatorBond;
+
+impl pallet_collator_offences::SlashCollator<AccountId> for SlashCollatorBond {
+    fn slash(who: &AccountId, fraction: Perbill) {
+        CollatorStaking::slash(who, fraction);
+    }
+}
+
+/// Makes benchmarked collators candidates at the minimum bond with the most delegators, so that
+/// a slash removes them and unbonds every delegation.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct OffencesBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_collator_offences::BenchmarkHelper<AccountId> for OffencesBenchmarkHelper {
+    fn setup_collator(who: &AccountId) {
+        use frame_support::traits::fungible::Mutate;
+
+        let bond = dynamic_params::staking::MinCandidateBond::get();
+        let delegation = dynamic_params::staking::MinDelegation::get();
+        Balances::set_balance(who, bond * 2);
+        let aura = sp_core::sr25519::Public::from_raw(who.clone().into());
+        pallet_session::NextKeys::<Runtime>::insert(who, SessionKeys { aura: aura.into() });
+        CollatorStaking::register_as_candidate(
+            RuntimeOrigin::signed(who.clone()),
+            bond,
+            Perbill::from_percent(10),
+        )
+        .expect("funded account with keys can register");
+
+        let delegators =
+            <Runtime as pallet_collator_staking::Config>::MaxDelegatorsPerCandidate::get();
+        for i in 0..delegators {
+            let delegator: AccountId = frame_benchmarking::account("delegator", i, 0);
+            Balances::set_balance(&delegator, delegation * 2);
+            CollatorStaking::delegate(RuntimeOrigin::signed(delegator), who.clone(), delegation)
+                .expect("funded account can delegate");
+        }
+    }
+
+    fn track_missed_slots() {
+        if dynamic_params::offences::MaxMissedSlots::get() == 0 {
+            crate::Parameters::set_parameter(
+                RuntimeOrigin::root(),
+                RuntimeParameters::Offences(dynamic_params::offences::Parameters::MaxMissedSlots(
+                    dynamic_params::offences::MaxMissedSlots,
+                    Some(50),
+                )),
+            )
+            .expect("root can set parameters");
+        }
+    }
+}
+
+parameter_types! {
+    // About one session at the default session period.
+    pub const EquivocationReportLongevity: u64 = 6 * HOURS as u64;
+}
+
+impl pallet_collator_offences::Config for Runtime {
+    type RuntimeEvent = RuntimeEvent;
+    type Slash = SlashCollatorBond;
+    type EquivocationSlash = dynamic_params::offences::EquivocationSlash;
+    type MaxMissedSlots = dynamic_params::offences::MaxMissedSlots;
+    type MissedSlotsSlash = dynamic_params::offences::MissedSlotsSlash;
+    type ReportLongevity = EquivocationReportLongevity;
+    type WeightInfo = weights::pallet_collator_offences::WeightInfo<Runtime>;
+    #[cfg(feature = "runtime-benchmarks")]
+    type BenchmarkHelper = OffencesBenchmarkHelper;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Parameters;
+    use pallet_session::ShouldEndSession;
+    use sp_runtime::BuildStorage;
+
+    fn new_test_ext() -> sp_io::TestExternalities {
+        frame_system::GenesisConfig::<Runtime>::default()
+            .build_storage()
+            .unwrap()
+            .into()
+    }
+
+    fn set_period(period: BlockNumber) {
+        Parameters::set_parameter(
+            RuntimeOrigin::root(),
+            RuntimeParameters::Session(dynamic_params::session::Parameters::Period(
+                dynamic_params::session::Period,
+                Some(period),
+            )),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn session_period_is_clamped() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(SessionPeriod::get(), 6 * HOURS);
+
+            set_period(0);
+            assert_eq!(SessionPeriod::get(), MIN_SESSION_PERIOD);
+            // Would divide by zero if the period was passed through as is.
+            assert!(!<Runtime as pallet_session::Config>::ShouldEndSession::should_end_session(1));
+            assert!(
+                <Runtime as pallet_session::Config>::ShouldEndSession::should_end_session(
+                    MIN_SESSION_PERIOD
+                )
+            );
+
+            set_period(BlockNumber::MAX);
+            assert_eq!(SessionPeriod::get(), MAX_SESSION_PERIOD);
+        });
+    }
+
+    #[test]
+    fn unbonding_outlasts_equivocation_reports() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(UnbondingSessions::get(), 28);
+
+            set_period(MIN_SESSION_PERIOD);
+            // Funds unbonded at the end of a session are held for all but that session.
+            let held = u64::from((UnbondingSessions::get() - 1) * MIN_SESSION_PERIOD);
+            assert!(held >= EquivocationReportLongevity::get());
+            assert_eq!(UnbondingSessions::get(), 37);
+        });
+    }
 }
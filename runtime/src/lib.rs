@@ -26,6 +26,7 @@ use sp_version::RuntimeVersion;
 
 use frame_support::{
     construct_runtime,
+    traits::Get,
     weights::{
         constants::WEIGHT_REF_TIME_PER_SECOND, Weight, WeightToFeeCoefficient,
         WeightToFeeCoefficients, WeightToFeePolynomial,
@@ -114,9 +115,9 @@ pub struct WeightToFee;
 impl WeightToFeePolynomial for WeightToFee {
     type Balance = Balance;
     fn polynomial() -> WeightToFeeCoefficients<Self::Balance> {
-        // in Rococo, extrinsic base weight (smallest non-zero weight) is mapped to 1 MILLIUNIT:
-        // in our template, we map to 1/10 of that, or 1/10 MILLIUNIT
-        let p = MILLIUNIT / 10;
+        // extrinsic base weight (smallest non-zero weight) is mapped to a governance-tunable fee,
+        // 1/10 MILLIUNIT by default
+        let p = configs::dynamic_params::transaction_payment::ExtrinsicBaseFee::get();
         let q = 100 * Balance::from(ExtrinsicBaseWeight::get().ref_time());
         smallvec![WeightToFeeCoefficient {
             degree: 1,
@@ -183,6 +184,8 @@ pub const SLOT_DURATION: u64 = MILLISECS_PER_BLOCK;
 pub const MINUTES: BlockNumber = 60_000 / (MILLISECS_PER_BLOCK as BlockNumber);
 pub const HOURS: BlockNumber = MINUTES * 60;
 pub const DAYS: BlockNumber = HOURS * 24;
+/// A Julian year of 365.25 days.
+pub const YEARS: BlockNumber = DAYS * 36525 / 100;
 
 // Unit = the base number of indivisible units for balances
 pub const UNIT: Balance = 1_000_000_000_000;
@@ -214,7 +217,7 @@ const UNINCLUDED_SEGMENT_CAPACITY: u32 = 3;
 /// number of blocks authored per slot.
 const BLOCK_PROCESSING_VELOCITY: u32 = 1;
 /// Relay chain slot duration, in milliseconds.
-const RELAY_CHAIN_SLOT_DURATION_MILLIS: u32 = 6000;
+pub const RELAY_CHAIN_SLOT_DURATION_MILLIS: u32 = 6000;
 
 /// Aura consensus hook
 type ConsensusHook = cumulus_pallet_aura_ext::FixedVelocityConsensusHook<
@@ -250,13 +253,16 @@ construct_runtime!(
 
         // Governance
         Sudo: pallet_sudo = 15,
+        Parameters: pallet_parameters = 16,
 
         // Collator support. The order of these 4 are important and shall not change.
         Authorship: pallet_authorship = 20,
-        CollatorSelection: pallet_collator_selection = 21,
+        CollatorStaking: pallet_collator_staking = 21,
         Session: pallet_session = 22,
         Aura: pallet_aura = 23,
         AuraExt: cumulus_pallet_aura_ext = 24,
+        // Must come after Aura, it reads the slot Aura sets in `on_initialize`.
+        CollatorOffences: pallet_collator_offences = 25,
 
         // XCM helpers.
         XcmpQueue: cumulus_pallet_xcmp_queue = 30,
@@ -19,6 +19,9 @@
 
 pub mod block_weights;
 pub mod extrinsic_weights;
+pub mod pallet_collator_offences;
+pub mod pallet_collator_staking;
+pub mod pallet_parameters;
 pub mod paritydb_weights;
 pub mod rocksdb_weights;
 
@@ -0,0 +1,276 @@
+
+//! Autogenerated weights for `pallet_collator_staking`
+//!
+//! THIS FILE WAS AUTO-GENERATED USING THE SUBSTRATE BENCHMARK CLI VERSION 37.0.0
+//! DATE: 2026-10-19, STEPS: `50`, REPEAT: `20`, LOW RANGE: `[]`, HIGH RANGE: `[]`
+//! WORST CASE MAP SIZE: `1000000`
+//! HOSTNAME: `vm`, CPU: `Intel(R) Xeon(R) Processor`
+//! WASM-EXECUTION: `Compiled`, CHAIN: `None`, DB CACHE: 1024
+
+// Executed Command:
+// ./bench-cli
+// --runtime
+// /tmp/bloc_runtime_bench.wasm
+// --genesis-builder
+// runtime
+// --pallet
+// pallet_collator_staking
+// --extrinsic
+// *
+// --steps
+// 50
+// --repeat
+// 20
+// --wasm-execution
+// compiled
+// --output
+// /root/crate/runtime/src/weights/pallet_collator_staking.rs
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::Weight};
+use core::marker::PhantomData;
+
+/// Weight functions for `pallet_collator_staking`.
+pub struct WeightInfo<T>(PhantomData<T>);
+impl<T: frame_system::Config> pallet_collator_staking::WeightInfo for WeightInfo<T> {
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::CounterForCandidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::CounterForCandidates` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Parameters::Parameters` (r:1 w:0)
+	/// Proof: `Parameters::Parameters` (`max_values`: None, `max_size`: Some(37), added: 2512, mode: `MaxEncodedLen`)
+	/// Storage: `Session::NextKeys` (r:1 w:0)
+	/// Proof: `Session::NextKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(67), added: 2542, mode: `MaxEncodedLen`)
+	fn register_as_candidate() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `114`
+		//  Estimated: `3579`
+		// Minimum execution time: 150_207_000 picoseconds.
+		Weight::from_parts(156_892_000, 0)
+			.saturating_add(Weight::from_parts(0, 3579))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(67), added: 2542, mode: `MaxEncodedLen`)
+	fn candidate_bond_more() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `186`
+		//  Estimated: `3561`
+		// Minimum execution time: 135_407_000 picoseconds.
+		Weight::from_parts(147_693_000, 0)
+			.saturating_add(Weight::from_parts(0, 3561))
+			.saturating_add(T::DbWeight::get().reads(2))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `Parameters::Parameters` (r:2 w:0)
+	/// Proof: `Parameters::Parameters` (`max_values`: None, `max_size`: Some(37), added: 2512, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::CurrentSession` (r:1 w:0)
+	/// Proof: `CollatorStaking::CurrentSession` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Unbonding` (r:1 w:1)
+	/// Proof: `CollatorStaking::Unbonding` (`max_values`: None, `max_size`: Some(361), added: 2836, mode: `MaxEncodedLen`)
+	fn candidate_bond_less() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `130`
+		//  Estimated: `6014`
+		// Minimum execution time: 61_032_000 picoseconds.
+		Weight::from_parts(69_548_000, 0)
+			.saturating_add(Weight::from_parts(0, 6014))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	fn set_commission() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `130`
+		//  Estimated: `3561`
+		// Minimum execution time: 26_894_000 picoseconds.
+		Weight::from_parts(29_394_000, 0)
+			.saturating_add(Weight::from_parts(0, 3561))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::CounterForCandidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::CounterForCandidates` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Delegations` (r:301 w:300)
+	/// Proof: `CollatorStaking::Delegations` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:301 w:301)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::UnclaimedRewards` (r:1 w:1)
+	/// Proof: `CollatorStaking::UnclaimedRewards` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::DelegatorTargets` (r:300 w:300)
+	/// Proof: `CollatorStaking::DelegatorTargets` (`max_values`: None, `max_size`: Some(297), added: 2772, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::CurrentSession` (r:1 w:0)
+	/// Proof: `CollatorStaking::CurrentSession` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Parameters::Parameters` (r:1 w:0)
+	/// Proof: `Parameters::Parameters` (`max_values`: None, `max_size`: Some(37), added: 2512, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Unbonding` (r:301 w:301)
+	/// Proof: `CollatorStaking::Unbonding` (`max_values`: None, `max_size`: Some(361), added: 2836, mode: `MaxEncodedLen`)
+	/// The range of component `d` is `[0, 300]`.
+	fn leave_candidates(d: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `133 + d * (294 ±0)`
+		//  Estimated: `3826 + d * (2836 ±0)`
+		// Minimum execution time: 83_094_000 picoseconds.
+		Weight::from_parts(87_665_000, 0)
+			.saturating_add(Weight::from_parts(0, 3826))
+			// Standard Error: 970_271
+			.saturating_add(Weight::from_parts(155_868_283, 0).saturating_mul(d.into()))
+			.saturating_add(T::DbWeight::get().reads(8))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(d.into())))
+			.saturating_add(T::DbWeight::get().writes(5))
+			.saturating_add(T::DbWeight::get().writes((4_u64).saturating_mul(d.into())))
+			.saturating_add(Weight::from_parts(0, 2836).saturating_mul(d.into()))
+	}
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Delegations` (r:1 w:1)
+	/// Proof: `CollatorStaking::Delegations` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::UnclaimedRewards` (r:1 w:1)
+	/// Proof: `CollatorStaking::UnclaimedRewards` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(67), added: 2542, mode: `MaxEncodedLen`)
+	fn delegate() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `530`
+		//  Estimated: `3593`
+		// Minimum execution time: 255_596_000 picoseconds.
+		Weight::from_parts(268_450_000, 0)
+			.saturating_add(Weight::from_parts(0, 3593))
+			.saturating_add(T::DbWeight::get().reads(5))
+			.saturating_add(T::DbWeight::get().writes(5))
+	}
+	/// Storage: `CollatorStaking::Delegations` (r:1 w:1)
+	/// Proof: `CollatorStaking::Delegations` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:1)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::UnclaimedRewards` (r:1 w:1)
+	/// Proof: `CollatorStaking::UnclaimedRewards` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::CurrentSession` (r:1 w:0)
+	/// Proof: `CollatorStaking::CurrentSession` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `Parameters::Parameters` (r:1 w:0)
+	/// Proof: `Parameters::Parameters` (`max_values`: None, `max_size`: Some(37), added: 2512, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Unbonding` (r:1 w:1)
+	/// Proof: `CollatorStaking::Unbonding` (`max_values`: None, `max_size`: Some(361), added: 2836, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::DelegatorTargets` (r:1 w:1)
+	/// Proof: `CollatorStaking::DelegatorTargets` (`max_values`: None, `max_size`: Some(297), added: 2772, mode: `MaxEncodedLen`)
+	fn undelegate() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `499`
+		//  Estimated: `3826`
+		// Minimum execution time: 193_497_000 picoseconds.
+		Weight::from_parts(206_324_000, 0)
+			.saturating_add(Weight::from_parts(0, 3826))
+			.saturating_add(T::DbWeight::get().reads(8))
+			.saturating_add(T::DbWeight::get().writes(6))
+	}
+	/// Storage: `CollatorStaking::Delegations` (r:1 w:1)
+	/// Proof: `CollatorStaking::Delegations` (`max_values`: None, `max_size`: Some(112), added: 2587, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Candidates` (r:1 w:0)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::UnclaimedRewards` (r:1 w:1)
+	/// Proof: `CollatorStaking::UnclaimedRewards` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	fn claim_rewards() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `437`
+		//  Estimated: `3593`
+		// Minimum execution time: 150_145_000 picoseconds.
+		Weight::from_parts(162_470_000, 0)
+			.saturating_add(Weight::from_parts(0, 3593))
+			.saturating_add(T::DbWeight::get().reads(4))
+			.saturating_add(T::DbWeight::get().writes(3))
+	}
+	/// Storage: `CollatorStaking::CurrentSession` (r:1 w:0)
+	/// Proof: `CollatorStaking::CurrentSession` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Unbonding` (r:1 w:1)
+	/// Proof: `CollatorStaking::Unbonding` (`max_values`: None, `max_size`: Some(361), added: 2836, mode: `MaxEncodedLen`)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	/// Proof: `Balances::Holds` (`max_values`: None, `max_size`: Some(67), added: 2542, mode: `MaxEncodedLen`)
+	fn withdraw_unbonded() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `244`
+		//  Estimated: `3826`
+		// Minimum execution time: 117_610_000 picoseconds.
+		Weight::from_parts(125_055_000, 0)
+			.saturating_add(Weight::from_parts(0, 3826))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().writes(2))
+	}
+	/// Storage: `CollatorStaking::BlocksAuthored` (r:1 w:1)
+	/// Proof: `CollatorStaking::BlocksAuthored` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	fn note_author() -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `49`
+		//  Estimated: `3509`
+		// Minimum execution time: 10_487_000 picoseconds.
+		Weight::from_parts(11_945_000, 0)
+			.saturating_add(Weight::from_parts(0, 3509))
+			.saturating_add(T::DbWeight::get().reads(1))
+			.saturating_add(T::DbWeight::get().writes(1))
+	}
+	/// Storage: `CollatorStaking::Candidates` (r:101 w:0)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// Storage: `Session::NextKeys` (r:100 w:0)
+	/// Proof: `Session::NextKeys` (`max_values`: None, `max_size`: None, mode: `Measured`)
+	/// Storage: `Parameters::Parameters` (r:1 w:0)
+	/// Proof: `Parameters::Parameters` (`max_values`: None, `max_size`: Some(37), added: 2512, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::CounterForCandidates` (r:1 w:0)
+	/// Proof: `CollatorStaking::CounterForCandidates` (`max_values`: Some(1), `max_size`: Some(4), added: 499, mode: `MaxEncodedLen`)
+	/// The range of component `c` is `[1, 100]`.
+	fn new_session(c: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `94 + c * (182 ±0)`
+		//  Estimated: `3561 + c * (2657 ±0)`
+		// Minimum execution time: 50_736_000 picoseconds.
+		Weight::from_parts(8_065_116, 0)
+			.saturating_add(Weight::from_parts(0, 3561))
+			// Standard Error: 310_898
+			.saturating_add(Weight::from_parts(17_358_061, 0).saturating_mul(c.into()))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(c.into())))
+			.saturating_add(Weight::from_parts(0, 2657).saturating_mul(c.into()))
+	}
+	/// Storage: `CollatorStaking::BlocksAuthored` (r:101 w:100)
+	/// Proof: `CollatorStaking::BlocksAuthored` (`max_values`: None, `max_size`: Some(44), added: 2519, mode: `MaxEncodedLen`)
+	/// Storage: `System::Account` (r:101 w:101)
+	/// Proof: `System::Account` (`max_values`: None, `max_size`: Some(128), added: 2603, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::UnclaimedRewards` (r:1 w:1)
+	/// Proof: `CollatorStaking::UnclaimedRewards` (`max_values`: Some(1), `max_size`: Some(16), added: 511, mode: `MaxEncodedLen`)
+	/// Storage: `CollatorStaking::Candidates` (r:100 w:100)
+	/// Proof: `CollatorStaking::Candidates` (`max_values`: None, `max_size`: Some(96), added: 2571, mode: `MaxEncodedLen`)
+	/// The range of component `r` is `[1, 100]`.
+	fn end_session(r: u32, ) -> Weight {
+		// Proof Size summary in bytes:
+		//  Measured:  `0 + r * (315 ±0)`
+		//  Estimated: `3593 + r * (2603 ±0)`
+		// Minimum execution time: 162_170_000 picoseconds.
+		Weight::from_parts(175_511_000, 0)
+			.saturating_add(Weight::from_parts(0, 3593))
+			// Standard Error: 1_184_553
+			.saturating_add(Weight::from_parts(134_528_868, 0).saturating_mul(r.into()))
+			.saturating_add(T::DbWeight::get().reads(3))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(r.into())))
+			.saturating_add(T::DbWeight::get().writes(2))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(r.into())))
+			.saturating_add(Weight::from_parts(0, 2603).saturating_mul(r.into()))
+	}
+}